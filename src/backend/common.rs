@@ -10,12 +10,13 @@ use thiserror::Error;
 
 use crate::{
     config::AStrSetExt,
-    hid::{get_keymap_wl, get_keymap_x11},
+    hid::{get_keymap_fallback, get_keymap_wl, get_keymap_x11},
     overlays::{
         anchor::create_anchor,
-        keyboard::{create_keyboard, KEYBOARD_NAME},
+        custom::create_custom,
+        keyboard::{create_keyboard, KeyboardSelection, KEYBOARD_NAME},
         screen::WlxClientAlias,
-        watch::{create_watch, WATCH_NAME},
+        watch::{create_watch, create_watch_canvas, WATCH_NAME},
     },
     state::AppState,
 };
@@ -114,6 +115,13 @@ where
             }
         }
 
+        app.keyboard_selection = KeyboardSelection::for_keymap(keymap.as_ref());
+        app.keymap = keymap.clone().or_else(|| {
+            get_keymap_fallback()
+                .map_err(|f| log::warn!("Could not load fallback keyboard layout: {f}"))
+                .ok()
+        });
+
         let anchor = create_anchor(app)?;
         overlays.insert(anchor.state.id.0, anchor);
 
@@ -121,10 +129,11 @@ where
         watch.state.want_visible = true;
         overlays.insert(watch.state.id.0, watch);
 
-        let mut keyboard = create_keyboard(app, keymap)?;
-        keyboard.state.show_hide = show_screens.arc_get(KEYBOARD_NAME);
-        keyboard.state.want_visible = false;
-        overlays.insert(keyboard.state.id.0, keyboard);
+        for mut keyboard in create_keyboard(app, keymap)? {
+            keyboard.state.show_hide = show_screens.arc_get(KEYBOARD_NAME);
+            keyboard.state.want_visible = false;
+            overlays.insert(keyboard.state.id.0, keyboard);
+        }
 
         Ok(Self { overlays, wl })
     }
@@ -137,9 +146,8 @@ where
     #[allow(clippy::too_many_lines, clippy::cognitive_complexity)]
     #[allow(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
     pub fn update(&mut self, app: &mut AppState) -> anyhow::Result<Vec<OverlayData<T>>> {
-        use crate::overlays::{
-            screen::{create_screen_interaction, create_screen_renderer_wl, load_pw_token_config},
-            watch::create_watch_canvas,
+        use crate::overlays::screen::{
+            create_screen_interaction, create_screen_renderer_wl, load_pw_token_config,
         };
         use glam::vec2;
         use wlx_capture::wayland::OutputChangeEvent;
@@ -207,6 +215,7 @@ where
                     overlay
                         .backend
                         .set_interaction(Box::new(create_screen_interaction(
+                            meta.name.clone(),
                             logical_pos,
                             logical_size,
                             transform,
@@ -270,6 +279,35 @@ where
         Ok(removed_overlays)
     }
 
+    /// Re-creates the canvases of the watch, keyboard and custom panels,
+    /// so that they pick up changes such as a new theme.
+    pub fn rebuild_panels(&mut self, app: &mut AppState) {
+        if let Some(watch) = self.mut_by_name(WATCH_NAME) {
+            match create_watch_canvas(None, app) {
+                Ok(canvas) => {
+                    watch.backend = Box::new(canvas);
+                }
+                Err(e) => {
+                    log::error!("Failed to create watch canvas: {e:?}");
+                }
+            }
+        }
+
+        // the keyboard replaces its own canvas, so that held keys get released
+        app.keyboard_selection.request_rebuild();
+
+        let panels = app.custom_panels.clone();
+        for name in panels {
+            let Some(panel) = self.mut_by_name(&name) else {
+                app.custom_panels.arc_rm(&name);
+                continue;
+            };
+            if let Some((_, backend)) = create_custom(app, name) {
+                panel.backend = backend;
+            }
+        }
+    }
+
     pub fn mut_by_selector(&mut self, selector: &OverlaySelector) -> Option<&mut OverlayData<T>> {
         match selector {
             OverlaySelector::Id(id) => self.mut_by_id(*id),
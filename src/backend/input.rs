@@ -37,6 +37,8 @@ pub struct InputState {
     pub ipd: f32,
     pub pointers: [Pointer; 2],
     pub devices: Vec<TrackedDevice>,
+    /// Played on both hands on the next frame
+    pub pending_haptics: Option<Haptics>,
     processes: Vec<Child>,
 }
 
@@ -47,6 +49,7 @@ impl InputState {
             ipd: 0.0,
             pointers: [Pointer::new(0), Pointer::new(1)],
             devices: Vec::new(),
+            pending_haptics: None,
             processes: Vec::new(),
         }
     }
@@ -315,15 +318,23 @@ pub fn interact<O>(
 where
     O: Default,
 {
-    if app.input_state.pointers[1].last_click > app.input_state.pointers[0].last_click {
-        let right = interact_hand(1, overlays, app);
-        let left = interact_hand(0, overlays, app);
-        [left, right]
-    } else {
-        let left = interact_hand(0, overlays, app);
-        let right = interact_hand(1, overlays, app);
-        [left, right]
+    let mut result =
+        if app.input_state.pointers[1].last_click > app.input_state.pointers[0].last_click {
+            let right = interact_hand(1, overlays, app);
+            let left = interact_hand(0, overlays, app);
+            [left, right]
+        } else {
+            let left = interact_hand(0, overlays, app);
+            let right = interact_hand(1, overlays, app);
+            [left, right]
+        };
+
+    if let Some(haptics) = app.input_state.pending_haptics.take() {
+        for (_, hand_haptics) in &mut result {
+            hand_haptics.get_or_insert_with(|| haptics.clone());
+        }
     }
+    result
 }
 
 #[allow(clippy::too_many_lines, clippy::cognitive_complexity)]
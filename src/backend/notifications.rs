@@ -1,50 +1,397 @@
+use chrono::{Local, NaiveTime};
 use dbus::{
-    arg::{PropMap, Variant},
+    arg::{PropMap, RefArg, Variant},
     blocking::Connection,
     channel::MatchingReceiver,
-    message::MatchRule,
+    message::{MatchRule, MessageType, SignalArgs},
 };
-use serde::Deserialize;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
 use std::{
+    collections::VecDeque,
+    net::{TcpListener, TcpStream},
+    path::{Path, PathBuf},
     sync::{
-        atomic::{AtomicBool, Ordering},
-        mpsc, Arc,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc, Arc, Mutex,
     },
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use crate::{
-    backend::notifications_dbus::OrgFreedesktopNotifications,
-    overlays::toast::{Toast, ToastTopic},
+    backend::notifications_dbus::{
+        OrgFreedesktopNotifications, OrgFreedesktopNotificationsOnDndToggle,
+    },
+    config::AStrMap,
+    config_io,
+    gui::{
+        icon::{is_icon_name, IconImage, IconSource},
+        modular::button::{ButtonAction, NotificationAction},
+    },
+    overlays::toast::{DisplayMethod, Toast, ToastTopic},
     state::AppState,
 };
 
+/// Action key of KDE-style inline replies
+const INLINE_REPLY_KEY: &str = "inline-reply";
+const NOTIFICATIONS_PATH: &str = "/org/freedesktop/Notifications";
+const MAKO_PATH: &str = "/fr/emersion/Mako";
+const MAKO_INTERFACE: &str = "fr.emersion.Mako";
+
+/// How many desktop notifications to remember for responding to and replacing
+const MAX_DBUS_SOURCES: usize = 32;
+
+const DND_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// XSOverlay clients may only pick sounds from this folder inside the config root
+const XSO_SOUNDS_DIR: &str = "sounds";
+/// Each OVR Toolkit API client gets its own thread
+const MAX_OVRT_CLIENTS: usize = 8;
+/// Loaded sounds are kept for the lifetime of the process, so only this many are loaded
+const MAX_XSO_SOUNDS: usize = 16;
+
+/// A notification received from the desktop or from XSOverlay clients
+pub struct Notification {
+    pub app_name: Arc<str>,
+    pub toast: Toast,
+    /// Not worth keeping once it has been shown, e.g. volume popups
+    pub transient: bool,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Urgency {
+    Low,
+    #[default]
+    Normal,
+    Critical,
+}
+
+impl Urgency {
+    const fn from_dbus(value: u64) -> Self {
+        match value {
+            0 => Self::Low,
+            2 => Self::Critical,
+            _ => Self::Normal,
+        }
+    }
+}
+
+/// A rule from `notification_rules` in the config.
+/// Every given condition must match. The first matching rule is applied.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct NotificationRuleConfig {
+    /// Regex on the name of the sending app
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app: Option<Arc<str>>,
+    /// Regex on the notification title
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<Arc<str>>,
+    /// Regex on the notification text
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<Arc<str>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub urgency: Option<Urgency>,
+
+    /// Where to show matching notifications. Hide to silence them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display: Option<DisplayMethod>,
+    /// In seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sound: Option<bool>,
+    /// WAV file to play instead of the notification sound, relative to the config dir
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sound_file: Option<Arc<str>>,
+}
+
+pub struct NotificationRule {
+    app: Option<Regex>,
+    summary: Option<Regex>,
+    body: Option<Regex>,
+    urgency: Option<Urgency>,
+    display: Option<DisplayMethod>,
+    timeout: Option<f32>,
+    sound: Option<bool>,
+    sound_wav: Option<&'static [u8]>,
+}
+
+impl NotificationRule {
+    /// Compiles the rules from the config, skipping any with invalid regexes.
+    pub fn load_all(configs: &[NotificationRuleConfig]) -> Vec<Self> {
+        configs
+            .iter()
+            .enumerate()
+            .filter_map(|(i, config)| {
+                Self::new(config)
+                    .map_err(|e| log::error!("Notification rule #{}: {e}", i + 1))
+                    .ok()
+            })
+            .collect()
+    }
+
+    fn new(config: &NotificationRuleConfig) -> anyhow::Result<Self> {
+        let regex = |pattern: &Option<Arc<str>>| pattern.as_deref().map(Regex::new).transpose();
+        Ok(Self {
+            app: regex(&config.app)?,
+            summary: regex(&config.summary)?,
+            body: regex(&config.body)?,
+            urgency: config.urgency,
+            display: config.display,
+            timeout: config.timeout,
+            sound: config.sound,
+            sound_wav: config
+                .sound_file
+                .as_deref()
+                .map(|path| AppState::try_load_bytes(path, include_bytes!("../res/557297.wav"))),
+        })
+    }
+
+    fn matches(&self, notification: &Notification) -> bool {
+        let test =
+            |regex: &Option<Regex>, text: &str| regex.as_ref().is_none_or(|r| r.is_match(text));
+        test(&self.app, &notification.app_name)
+            && test(&self.summary, &notification.toast.title)
+            && test(&self.body, &notification.toast.body)
+            && self.urgency.is_none_or(|u| u == notification.toast.urgency)
+    }
+
+    fn apply(&self, mut toast: Toast) -> Toast {
+        if let Some(display) = self.display {
+            toast = toast.with_display(display);
+        }
+        if let Some(timeout) = self.timeout {
+            toast = toast.with_timeout(timeout);
+        }
+        if let Some(sound) = self.sound {
+            toast = toast.with_sound(sound);
+        }
+        if let Some(wav) = self.sound_wav {
+            toast = toast.with_custom_sound(wav);
+        }
+        toast
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum NotificationResponse {
+    Action(Arc<str>),
+}
+
+/// Where a desktop notification came from, so that action clicks can be sent back.
+struct DbusSource {
+    handle: u64,
+    sender: Arc<str>,
+    serial: u32,
+    /// Assigned by the notification server once it replies to the `Notify` call
+    id: Option<u32>,
+    /// Shared by all notifications that replace each other
+    tag: u64,
+}
+
+/// How responses reach the app that sent a notification.
+/// Apps only accept `ActionInvoked` from the notification server itself,
+/// so the server has to be asked to emit it on our behalf.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum ResponseRoute {
+    /// The server offers no way to invoke actions remotely
+    #[default]
+    None,
+    /// `fr.emersion.Mako.InvokeAction`
+    Mako,
+}
+
+impl ResponseRoute {
+    fn from_server_name(name: &str) -> Self {
+        match name {
+            "mako" => Self::Mako,
+            _ => Self::None,
+        }
+    }
+
+    const fn supports_actions(self) -> bool {
+        matches!(self, Self::Mako)
+    }
+}
+
+#[derive(Default)]
+struct DbusSources {
+    next_handle: u64,
+    entries: VecDeque<DbusSource>,
+    route: ResponseRoute,
+}
+
+impl DbusSources {
+    /// Returns the handle of the new source and its replacement tag.
+    fn add(&mut self, sender: Arc<str>, serial: u32, replaces_id: u32) -> (u64, u64) {
+        let handle = self.next_handle;
+        self.next_handle += 1;
+        let tag = self
+            .entries
+            .iter()
+            .find(|s| replaces_id != 0 && s.id == Some(replaces_id))
+            .map_or(handle, |s| s.tag);
+        self.entries.push_front(DbusSource {
+            handle,
+            sender,
+            serial,
+            id: None,
+            tag,
+        });
+        self.entries.truncate(MAX_DBUS_SOURCES);
+        (handle, tag)
+    }
+
+    fn on_method_return(&mut self, msg: &dbus::Message) {
+        let (Some(destination), Some(reply_serial)) = (msg.destination(), msg.get_reply_serial())
+        else {
+            return;
+        };
+        if let Some(source) = self
+            .entries
+            .iter_mut()
+            .find(|s| s.id.is_none() && s.serial == reply_serial && *s.sender == *destination)
+        {
+            source.id = msg.read1::<u32>().ok();
+        }
+    }
+}
+
 pub struct NotificationManager {
-    rx_toast: mpsc::Receiver<Toast>,
-    tx_toast: mpsc::SyncSender<Toast>,
+    rx_toast: mpsc::Receiver<Notification>,
+    tx_toast: mpsc::SyncSender<Notification>,
     dbus_data: Option<Connection>,
+    /// Monitoring connections may not send, so responses go through this one
+    dbus_emitter: Option<Connection>,
+    dbus_sources: Arc<Mutex<DbusSources>>,
+    /// DND changes announced by the notification daemon
+    rx_dnd: mpsc::Receiver<bool>,
+    tx_dnd: mpsc::Sender<bool>,
     running: Arc<AtomicBool>,
 }
 
 impl NotificationManager {
     pub fn new() -> Self {
         let (tx_toast, rx_toast) = mpsc::sync_channel(10);
+        let (tx_dnd, rx_dnd) = mpsc::channel();
         Self {
             rx_toast,
             tx_toast,
             dbus_data: None,
+            dbus_emitter: None,
+            dbus_sources: Arc::new(Mutex::new(DbusSources::default())),
+            rx_dnd,
+            tx_dnd,
             running: Arc::new(AtomicBool::new(true)),
         }
     }
 
+    /// Asks the notification server to deliver the response to the app that sent the notification.
+    pub fn respond(&self, handle: u64, response: &NotificationResponse) {
+        let Some(c) = &self.dbus_emitter else {
+            return;
+        };
+
+        let (route, source) = {
+            let sources = self.dbus_sources.lock().unwrap(); // want panic
+            let source = sources
+                .entries
+                .iter()
+                .find(|s| s.handle == handle)
+                .and_then(|s| s.id);
+            (sources.route, source)
+        };
+        let Some(id) = source else {
+            log::warn!("Notification {handle} is no longer known, not responding.");
+            return;
+        };
+
+        match (route, response) {
+            (ResponseRoute::Mako, NotificationResponse::Action(key)) => {
+                let proxy = c.with_proxy(
+                    "org.freedesktop.Notifications",
+                    MAKO_PATH,
+                    Duration::from_millis(500),
+                );
+                let result: Result<(), dbus::Error> =
+                    proxy.method_call(MAKO_INTERFACE, "InvokeAction", (id, key.to_string()));
+                if let Err(e) = result {
+                    log::error!("Failed to invoke action {key} on notification {id}: {e:?}");
+                }
+            }
+            _ => {
+                log::warn!("Notification server cannot deliver {response:?}, not responding.");
+            }
+        }
+    }
+
+    /// Picks the way responses are delivered based on the running notification server
+    fn detect_response_route(&self, c: &Connection) {
+        let proxy = c.with_proxy(
+            "org.freedesktop.Notifications",
+            NOTIFICATIONS_PATH,
+            Duration::from_millis(500),
+        );
+        let route = match proxy.get_server_information() {
+            Ok((name, ..)) => {
+                let route = ResponseRoute::from_server_name(&name);
+                log::info!("Notification server is {name}, responses via {route:?}.");
+                route
+            }
+            Err(e) => {
+                log::debug!("Failed to query notification server: {e:?}");
+                ResponseRoute::None
+            }
+        };
+        self.dbus_sources.lock().unwrap().route = route; // want panic
+    }
+
     pub fn submit_pending(&self, app: &mut AppState) {
         if let Some(c) = &self.dbus_data {
             let _ = c.process(Duration::ZERO);
         }
+        if let Some(c) = &self.dbus_emitter {
+            let _ = c.process(Duration::ZERO);
+        }
+
+        if let Some(enabled) = self.rx_dnd.try_iter().last() {
+            set_dnd(app, enabled, DndSource::Daemon);
+        }
+        update_dnd_schedule(app);
+        if app.dnd.sync_pending {
+            app.dnd.sync_pending = false;
+            self.push_dnd(app.dnd.enabled);
+        }
 
         if app.session.config.notifications_enabled {
-            self.rx_toast.try_iter().for_each(|toast| {
-                toast.submit(app);
+            self.rx_toast.try_iter().for_each(|notification| {
+                if !notification.transient {
+                    app.notification_history.push(&notification);
+                }
+
+                let rule = app
+                    .session
+                    .notification_rules
+                    .iter()
+                    .find(|r| r.matches(&notification));
+                let notification = match rule {
+                    Some(rule) => Notification {
+                        toast: rule.apply(notification.toast),
+                        ..notification
+                    },
+                    None => notification,
+                };
+
+                if app.dnd.enabled && notification.toast.urgency != Urgency::Critical {
+                    // nothing to catch up on for notifications that would not be shown anyway
+                    if !notification.transient
+                        && notification.toast.display_method(app) != DisplayMethod::Hide
+                    {
+                        app.dnd.hold(&notification);
+                    }
+                    return;
+                }
+                notification.toast.submit(app);
             });
         } else {
             // consume without submitting
@@ -52,6 +399,50 @@ impl NotificationManager {
         }
     }
 
+    /// Tells the notification daemon about our DND state, if it supports DND.
+    fn push_dnd(&self, enabled: bool) {
+        let Some(c) = &self.dbus_emitter else {
+            return;
+        };
+        let proxy = c.with_proxy(
+            "org.freedesktop.Notifications",
+            NOTIFICATIONS_PATH,
+            Duration::from_millis(500),
+        );
+        if let Err(e) = proxy.set_dnd(enabled) {
+            log::debug!("Notification daemon does not support DND: {e:?}");
+        }
+    }
+
+    fn init_dnd_sync(&self, c: &Connection) {
+        let proxy = c.with_proxy(
+            "org.freedesktop.Notifications",
+            NOTIFICATIONS_PATH,
+            Duration::from_millis(500),
+        );
+        match proxy.get_dnd() {
+            Ok(enabled) => {
+                let _ = self.tx_dnd.send(enabled);
+            }
+            Err(e) => {
+                log::debug!("Notification daemon does not support DND: {e:?}");
+                return;
+            }
+        }
+
+        let tx_dnd = self.tx_dnd.clone();
+        let result = c.add_match(
+            OrgFreedesktopNotificationsOnDndToggle::match_rule(None, None),
+            move |signal: OrgFreedesktopNotificationsOnDndToggle, _, _| {
+                let _ = tx_dnd.send(signal.dnd);
+                true
+            },
+        );
+        if let Err(e) = result {
+            log::warn!("Failed to listen for DND changes: {e:?}");
+        }
+    }
+
     pub fn run_dbus(&mut self) {
         let c = match Connection::new_session() {
             Ok(c) => c,
@@ -69,6 +460,23 @@ impl NotificationManager {
         rule.path = Some("/org/freedesktop/Notifications".into());
         rule.eavesdrop = true;
 
+        // replies to Notify carry the id that the server assigned
+        let mut return_rule = MatchRule::new();
+        return_rule.msg_type = Some(MessageType::MethodReturn);
+        return_rule.eavesdrop = true;
+        // the bus resolves well-known names, the local matcher does not
+        let mut bus_return_rule = return_rule.clone();
+        bus_return_rule.sender = Some("org.freedesktop.Notifications".into());
+
+        match Connection::new_session() {
+            Ok(emitter) => {
+                self.init_dnd_sync(&emitter);
+                self.detect_response_route(&emitter);
+                self.dbus_emitter = Some(emitter);
+            }
+            Err(e) => log::warn!("Notification actions and DND sync will not work: {e:?}"),
+        }
+
         let proxy = c.with_proxy(
             "org.freedesktop.DBus",
             "/org/freedesktop/DBus",
@@ -77,16 +485,26 @@ impl NotificationManager {
         let result: Result<(), dbus::Error> = proxy.method_call(
             "org.freedesktop.DBus.Monitoring",
             "BecomeMonitor",
-            (vec![rule.match_str()], 0u32),
+            (vec![rule.match_str(), bus_return_rule.match_str()], 0u32),
+        );
+
+        let sources = self.dbus_sources.clone();
+        c.start_receive(
+            return_rule,
+            Box::new(move |msg, _| {
+                sources.lock().unwrap().on_method_return(&msg); // want panic
+                true
+            }),
         );
 
         if matches!(result, Ok(())) {
             let sender = self.tx_toast.clone();
+            let sources = self.dbus_sources.clone();
             c.start_receive(
                 rule,
                 Box::new(move |msg, _| {
-                    if let Ok(toast) = parse_dbus(&msg) {
-                        match sender.try_send(toast) {
+                    if let Ok(notification) = parse_dbus(&msg, &sources) {
+                        match sender.try_send(notification) {
                             Ok(()) => {}
                             Err(e) => {
                                 log::error!("Failed to send notification: {e:?}");
@@ -104,10 +522,15 @@ impl NotificationManager {
                 rule
             };
 
+            if let Err(e) = c.add_match_no_cb(&bus_return_rule.match_str()) {
+                log::warn!("Failed to add DBus match for notification ids: {e:?}");
+            }
+
             let sender2 = self.tx_toast.clone();
+            let sources = self.dbus_sources.clone();
             let result = c.add_match(rule_with_eavesdrop, move |(): (), _, msg| {
-                if let Ok(toast) = parse_dbus(msg) {
-                    match sender2.try_send(toast) {
+                if let Ok(notification) = parse_dbus(msg, &sources) {
+                    match sender2.try_send(notification) {
                         Ok(()) => {}
                         Err(e) => {
                             log::error!("Failed to send notification: {e:?}");
@@ -130,12 +553,15 @@ impl NotificationManager {
         self.dbus_data = Some(c);
     }
 
-    pub fn run_udp(&mut self) {
+    pub fn run_udp(&mut self, addr: Arc<str>) {
+        if addr.is_empty() {
+            log::info!("XSOverlay notification listener is disabled.");
+            return;
+        }
         let sender = self.tx_toast.clone();
         let running = self.running.clone();
         let _ = std::thread::spawn(move || {
-            let addr = "127.0.0.1:42069";
-            let socket = match std::net::UdpSocket::bind(addr) {
+            let socket = match std::net::UdpSocket::bind(&*addr) {
                 Ok(s) => s,
                 Err(e) => {
                     log::error!("Failed to bind notification socket @ {addr}: {e:?}");
@@ -146,7 +572,9 @@ impl NotificationManager {
                 log::error!("Failed to set read timeout: {err:?}");
             }
 
-            let mut buf = [0u8; 1024 * 16]; // vrcx embeds icons as b64
+            // largest possible UDP payload; vrcx embeds icons as b64
+            let mut buf = vec![0u8; u16::MAX as usize];
+            let mut sounds = AStrMap::new();
 
             while running.load(Ordering::Relaxed) {
                 if let Ok((num_bytes, _)) = socket.recv_from(&mut buf) {
@@ -165,19 +593,11 @@ impl NotificationManager {
                         }
                     };
 
-                    if msg.messageType != 1 {
+                    let Some(notification) = parse_xso(msg, &mut sounds) else {
                         continue;
-                    }
-
-                    let toast = Toast::new(
-                        ToastTopic::XSNotification,
-                        msg.title,
-                        msg.content.unwrap_or_else(|| "".into()),
-                    )
-                    .with_timeout(msg.timeout.unwrap_or(5.))
-                    .with_sound(msg.volume.unwrap_or(-1.) >= 0.); // XSOverlay still plays at 0,
+                    };
 
-                    match sender.try_send(toast) {
+                    match sender.try_send(notification) {
                         Ok(()) => {}
                         Err(e) => {
                             log::error!("Failed to send notification: {e:?}");
@@ -188,6 +608,58 @@ impl NotificationManager {
             log::info!("Notification listener stopped.");
         });
     }
+
+    /// Hosts an OVR Toolkit compatible WebSocket API, for tools that send notifications there.
+    pub fn run_ovrt(&mut self, addr: Arc<str>) {
+        if addr.is_empty() {
+            return;
+        }
+        let sender = self.tx_toast.clone();
+        let running = self.running.clone();
+        let clients = Arc::new(AtomicUsize::new(0));
+        let _ = std::thread::spawn(move || {
+            let listener = match TcpListener::bind(&*addr) {
+                Ok(l) => l,
+                Err(e) => {
+                    log::error!("Failed to bind OVR Toolkit API socket @ {addr}: {e:?}");
+                    return;
+                }
+            };
+            if let Err(err) = listener.set_nonblocking(true) {
+                log::error!("Failed to set OVR Toolkit API socket to non-blocking: {err:?}");
+                return;
+            }
+
+            while running.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, peer)) => {
+                        if clients.load(Ordering::Relaxed) >= MAX_OVRT_CLIENTS {
+                            log::warn!("Too many OVR Toolkit API clients, refusing {peer}");
+                            continue;
+                        }
+                        log::debug!("OVR Toolkit API client connected: {peer}");
+                        clients.fetch_add(1, Ordering::Relaxed);
+                        let sender = sender.clone();
+                        let running = running.clone();
+                        let clients = clients.clone();
+                        let _ = std::thread::spawn(move || {
+                            if let Err(e) = run_ovrt_client(stream, &sender, &running) {
+                                log::debug!("OVR Toolkit API client {peer}: {e:?}");
+                            }
+                            clients.fetch_sub(1, Ordering::Relaxed);
+                        });
+                    }
+                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                        std::thread::sleep(Duration::from_millis(200));
+                    }
+                    Err(e) => {
+                        log::error!("Failed to accept OVR Toolkit API client: {e:?}");
+                    }
+                }
+            }
+            log::info!("OVR Toolkit API listener stopped.");
+        });
+    }
 }
 
 impl Drop for NotificationManager {
@@ -196,6 +668,258 @@ impl Drop for NotificationManager {
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DndSchedule {
+    /// Local time as HH:MM
+    pub from: Arc<str>,
+    /// Local time as HH:MM. May be earlier than `from` to span midnight.
+    pub to: Arc<str>,
+}
+
+impl DndSchedule {
+    fn contains(&self, now: NaiveTime) -> anyhow::Result<bool> {
+        let from = NaiveTime::parse_from_str(&self.from, "%H:%M")?;
+        let to = NaiveTime::parse_from_str(&self.to, "%H:%M")?;
+        Ok(if from <= to {
+            from <= now && now < to
+        } else {
+            now >= from || now < to
+        })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum DndSource {
+    User,
+    Schedule,
+    Daemon,
+}
+
+/// Do not disturb: notifications are held back while enabled,
+/// and summarized once it ends. Critical notifications still show.
+#[derive(Default)]
+pub struct DoNotDisturb {
+    pub enabled: bool,
+    /// App names and tags of the notifications that were held back
+    held: Vec<(Arc<str>, Option<Arc<str>>)>,
+    /// Whether the schedule was active on the last check
+    in_schedule: Option<bool>,
+    last_check: Option<Instant>,
+    /// The daemon needs to be told about a change
+    sync_pending: bool,
+}
+
+impl DoNotDisturb {
+    /// A replacement of a notification that is already held back is not counted again
+    fn hold(&mut self, notification: &Notification) {
+        let tag = notification.toast.tag.as_ref();
+        if tag.is_some() && self.held.iter().any(|(_, t)| t.as_ref() == tag) {
+            return;
+        }
+        self.held
+            .push((notification.app_name.clone(), tag.cloned()));
+    }
+}
+
+pub fn set_dnd(app: &mut AppState, enabled: bool, source: DndSource) {
+    if app.dnd.enabled == enabled {
+        return;
+    }
+    app.dnd.enabled = enabled;
+    app.dnd.sync_pending = source != DndSource::Daemon;
+    log::info!("Do not disturb: {enabled}");
+
+    #[cfg(feature = "osc")]
+    if let Some(sender) = app.osc_sender.as_mut() {
+        let _ = sender.send_single_param(
+            "/avatar/parameters/isDndEnabled".into(),
+            vec![rosc::OscType::Bool(enabled)],
+        );
+    }
+
+    if enabled {
+        if source != DndSource::Daemon {
+            Toast::new(
+                ToastTopic::System,
+                "Do not disturb is on.".into(),
+                "".into(),
+            )
+            .submit(app);
+        }
+        return;
+    }
+
+    let held = std::mem::take(&mut app.dnd.held);
+    if held.is_empty() {
+        if source != DndSource::Daemon {
+            Toast::new(
+                ToastTopic::System,
+                "Do not disturb is off.".into(),
+                "".into(),
+            )
+            .submit(app);
+        }
+        return;
+    }
+
+    let mut counts: AStrMap<usize> = AStrMap::new();
+    for (app_name, _) in &held {
+        match counts.iter_mut().find(|(k, _)| *k == *app_name) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((app_name.clone(), 1)),
+        }
+    }
+    let body = counts
+        .iter()
+        .map(|(app_name, count)| format!("{count} from {app_name}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Toast::new(
+        ToastTopic::System,
+        format!(
+            "{} notification{} while you were away",
+            held.len(),
+            if held.len() == 1 { "" } else { "s" }
+        )
+        .into(),
+        body.into(),
+    )
+    .with_timeout(10.)
+    .with_sound(true)
+    .submit(app);
+}
+
+/// Switches DND on or off at the edges of the configured schedule.
+fn update_dnd_schedule(app: &mut AppState) {
+    if app
+        .dnd
+        .last_check
+        .is_some_and(|t| t.elapsed() < DND_CHECK_INTERVAL)
+    {
+        return;
+    }
+    app.dnd.last_check = Some(Instant::now());
+
+    let Some(schedule) = app.session.config.dnd_schedule.as_ref() else {
+        return;
+    };
+    let in_schedule = match schedule.contains(Local::now().time()) {
+        Ok(in_schedule) => in_schedule,
+        Err(e) => {
+            log::error!("Invalid dnd_schedule: {e}");
+            app.session.config.dnd_schedule = None;
+            return;
+        }
+    };
+
+    // only act on changes, so that the user can still override in between
+    let changed = match app.dnd.in_schedule.replace(in_schedule) {
+        Some(was) => was != in_schedule,
+        None => in_schedule,
+    };
+    if changed {
+        set_dnd(app, in_schedule, DndSource::Schedule);
+    }
+}
+
+const HISTORY_FILE: &str = "notification_history.json";
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HistoryEntry {
+    pub id: u64,
+    /// Unix time in seconds
+    pub timestamp: i64,
+    pub app_name: Arc<str>,
+    pub title: Arc<str>,
+    pub body: Arc<str>,
+    /// Replacements of the notification update this entry. Only valid for this session.
+    #[serde(skip)]
+    tag: Option<Arc<str>>,
+}
+
+/// Notifications received so far, newest first. Kept across restarts.
+pub struct NotificationHistory {
+    entries: VecDeque<HistoryEntry>,
+    max_len: usize,
+    next_id: u64,
+}
+
+fn get_history_path() -> PathBuf {
+    config_io::get_config_root().join(HISTORY_FILE)
+}
+
+impl NotificationHistory {
+    pub fn load(max_len: usize) -> Self {
+        let entries = config_io::load(HISTORY_FILE)
+            .and_then(|json| {
+                serde_json::from_str::<VecDeque<HistoryEntry>>(&json)
+                    .map_err(|e| log::warn!("Failed to parse {HISTORY_FILE}: {e}"))
+                    .ok()
+            })
+            .unwrap_or_default();
+
+        let next_id = entries.iter().map(|e| e.id + 1).max().unwrap_or(0);
+        let mut me = Self {
+            entries,
+            max_len,
+            next_id,
+        };
+        me.entries.truncate(max_len);
+        me
+    }
+
+    pub fn push(&mut self, notification: &Notification) {
+        if self.max_len == 0 {
+            return;
+        }
+        let tag = notification.toast.tag.as_ref();
+        if let Some(entry) = self
+            .entries
+            .iter_mut()
+            .find(|e| tag.is_some() && e.tag.as_ref() == tag)
+        {
+            entry.timestamp = Local::now().timestamp();
+            entry.title = notification.toast.title.clone();
+            entry.body = notification.toast.body.clone();
+            self.save();
+            return;
+        }
+        self.entries.push_front(HistoryEntry {
+            id: self.next_id,
+            timestamp: Local::now().timestamp(),
+            app_name: notification.app_name.clone(),
+            title: notification.toast.title.clone(),
+            body: notification.toast.body.clone(),
+            tag: tag.cloned(),
+        });
+        self.next_id += 1;
+        self.entries.truncate(self.max_len);
+        self.save();
+    }
+
+    pub fn get(&self, idx: usize) -> Option<&HistoryEntry> {
+        self.entries.get(idx)
+    }
+
+    pub fn dismiss(&mut self, id: u64) {
+        self.entries.retain(|e| e.id != id);
+        self.save();
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.save();
+    }
+
+    fn save(&self) {
+        let json = serde_json::to_string(&self.entries).unwrap(); // want panic
+        if let Err(e) = std::fs::write(get_history_path(), json) {
+            log::warn!("Failed to save notification history: {e:?}");
+        }
+    }
+}
+
 pub struct DbusNotificationSender {
     connection: Connection,
 }
@@ -250,28 +974,373 @@ impl DbusNotificationSender {
     }
 }
 
-fn parse_dbus(msg: &dbus::Message) -> anyhow::Result<Toast> {
+fn parse_dbus(msg: &dbus::Message, sources: &Mutex<DbusSources>) -> anyhow::Result<Notification> {
     let mut args = msg.iter_init();
     let app_name: String = args.read()?;
-    let _replaces_id: u32 = args.read()?;
-    let _app_icon: String = args.read()?;
+    let replaces_id: u32 = args.read()?;
+    let app_icon: String = args.read()?;
     let summary: String = args.read()?;
     let body: String = args.read()?;
+    let actions: Vec<String> = args.read().unwrap_or_default();
+    let hints: PropMap = args.read().unwrap_or_default();
+    let transient = hints
+        .get("transient")
+        .and_then(|v| v.0.as_u64())
+        .is_some_and(|t| t != 0);
 
     let title = if summary.is_empty() {
-        app_name
+        app_name.clone()
     } else {
         summary
     };
 
-    Ok(
-        Toast::new(ToastTopic::DesktopNotification, title.into(), body.into())
-            .with_timeout(5.0)
-            .with_opacity(1.0),
-    )
+    let urgency = hints
+        .get("urgency")
+        .and_then(|v| v.0.as_u64())
+        .map_or(Urgency::Normal, Urgency::from_dbus);
+
+    let mut toast = Toast::new(ToastTopic::DesktopNotification, title.into(), body.into())
+        .with_timeout(5.0)
+        .with_opacity(1.0)
+        .with_urgency(urgency);
+
+    // volume and brightness popups, downloads, etc.
+    if let Some(value) = hints.get("value").and_then(|v| v.0.as_i64()) {
+        toast = toast.with_progress(value as f32 / 100.);
+    }
+
+    if let Some(icon) = parse_dbus_icon(&app_icon, &hints) {
+        toast = toast.with_icon(icon);
+    }
+
+    // actions come in pairs of key and label
+    let actions: Vec<(&str, &str)> = actions
+        .chunks_exact(2)
+        .map(|pair| (pair[0].as_str(), pair[1].as_str()))
+        .collect();
+
+    let Some((sender, serial)) = msg.sender().zip(msg.get_serial()) else {
+        return Ok(Notification {
+            app_name: app_name.into(),
+            toast,
+            transient,
+        });
+    };
+    let ((handle, tag), route) = {
+        let mut sources = sources.lock().unwrap(); // want panic
+        let added = sources.add(Arc::from(&*sender), serial, replaces_id);
+        (added, sources.route)
+    };
+    toast = toast.with_tag(format!("dbus-{tag}").into());
+
+    // only offer what can actually be delivered.
+    // inline replies are left out, no server can forward them
+    let actions: Vec<(&str, &str)> = actions
+        .into_iter()
+        .filter(|(key, _)| *key != INLINE_REPLY_KEY && route.supports_actions())
+        .collect();
+
+    if !actions.is_empty() {
+        for (key, label) in actions {
+            let label = if label.is_empty() { key } else { label };
+            toast = toast.with_action(
+                label.into(),
+                ButtonAction::Notifications {
+                    action: NotificationAction::Invoke {
+                        notification: handle,
+                        key: key.into(),
+                    },
+                },
+            );
+        }
+        // give some time to reach for the buttons
+        toast = toast.with_timeout(15.0);
+    }
+
+    Ok(Notification {
+        app_name: app_name.into(),
+        toast,
+        transient,
+    })
     // leave the audio part to the desktop env
 }
 
+/// Picks the icon in the order given by the notification spec:
+/// `image-data`, `image-path`, `app_icon`, then the deprecated `icon_data`.
+fn parse_dbus_icon(app_icon: &str, hints: &PropMap) -> Option<IconSource> {
+    let image_data = |key: &str| {
+        hints.get(key).and_then(|v| {
+            parse_image_data(&*v.0)
+                .map_err(|e| log::debug!("Could not read {key}: {e:?}"))
+                .ok()
+        })
+    };
+
+    if let Some(image) = image_data("image-data").or_else(|| image_data("image_data")) {
+        return Some(IconSource::Image(image));
+    }
+    let image_path = ["image-path", "image_path"]
+        .iter()
+        .find_map(|key| hints.get(*key).and_then(|v| v.0.as_str()))
+        .filter(|path| !path.is_empty());
+    if let Some(path) = image_path {
+        return Some(IconSource::from_name_or_path(path));
+    }
+    if !app_icon.is_empty() {
+        return Some(IconSource::from_name_or_path(app_icon));
+    }
+    image_data("icon_data").map(IconSource::Image)
+}
+
+/// Reads an `(iiibiiay)` image: width, height, rowstride, has alpha, bits per sample, channels, data
+fn parse_image_data(value: &dyn RefArg) -> anyhow::Result<IconImage> {
+    let Some(mut fields) = value.as_iter() else {
+        anyhow::bail!("not a struct");
+    };
+    let header: Vec<Option<i64>> = fields.by_ref().take(6).map(|f| f.as_i64()).collect();
+    let [Some(width), Some(height), Some(rowstride), _, Some(bits_per_sample), Some(channels)] =
+        header[..]
+    else {
+        anyhow::bail!("bad header");
+    };
+
+    if bits_per_sample != 8 {
+        anyhow::bail!("unsupported bits per sample: {bits_per_sample}");
+    }
+
+    let Some(data) = fields.next() else {
+        anyhow::bail!("missing data");
+    };
+    let data: Vec<u8> = if let Some(bytes) = dbus::arg::cast::<Vec<u8>>(data) {
+        bytes.clone()
+    } else {
+        data.as_iter()
+            .ok_or_else(|| anyhow::anyhow!("data is not an array"))?
+            .map(|b| b.as_u64().unwrap_or(0) as u8)
+            .collect()
+    };
+
+    IconImage::from_pixels(
+        u32::try_from(width)?,
+        u32::try_from(height)?,
+        usize::try_from(rowstride)?,
+        usize::try_from(channels)?,
+        &data,
+    )
+}
+
+fn parse_xso(msg: XsoMessage, sounds: &mut AStrMap<&'static [u8]>) -> Option<Notification> {
+    match msg.messageType {
+        1 => {}
+        // 2 is media player info, which XSOverlay only uses for its own wrist widget
+        other => {
+            log::debug!("Ignoring XSOverlay message of type {other}");
+            return None;
+        }
+    }
+
+    let mut toast = Toast::new(
+        ToastTopic::XSNotification,
+        msg.title,
+        msg.content.unwrap_or_else(|| "".into()),
+    )
+    .with_timeout(
+        msg.timeout
+            .filter(|t| t.is_finite())
+            .unwrap_or(5.)
+            .clamp(0.5, 60.),
+    )
+    .with_opacity(msg.opacity.unwrap_or(1.).clamp(0., 1.))
+    .with_min_height(msg.height.unwrap_or(0.).max(0.))
+    .with_sound(msg.volume.unwrap_or(-1.) >= 0.); // XSOverlay still plays at 0,
+
+    if let Some(wav) = parse_xso_sound(msg.audioPath.as_deref(), sounds) {
+        toast = toast.with_custom_sound(wav);
+    }
+
+    if let Some(icon) = parse_xso_icon(msg.icon.as_deref(), msg.useBase64Icon) {
+        toast = toast.with_icon(icon);
+    }
+
+    Some(Notification {
+        app_name: msg.sourceApp.unwrap_or_else(|| "XSOverlay".into()),
+        toast,
+        transient: false,
+    })
+}
+
+/// Loads a custom sound once per path. `None` plays the default sound.
+fn parse_xso_sound(
+    path: Option<&str>,
+    sounds: &mut AStrMap<&'static [u8]>,
+) -> Option<&'static [u8]> {
+    let path = path.filter(|p| !p.is_empty())?;
+    // XSOverlay's built-in sounds
+    if matches!(path, "default" | "error" | "warning") {
+        return None;
+    }
+    if let Some((_, wav)) = sounds.iter().find(|(p, _)| &**p == path) {
+        return Some(*wav);
+    }
+    // clients are remote, keep them from reading anything outside the sounds folder
+    if !config_io::is_contained(path) {
+        log::warn!("Rejecting XSOverlay sound outside of {XSO_SOUNDS_DIR}: {path}");
+        return None;
+    }
+    if sounds.len() >= MAX_XSO_SOUNDS {
+        log::warn!("Too many XSOverlay sounds loaded, using the default for: {path}");
+        return None;
+    }
+    let file = Path::new(XSO_SOUNDS_DIR).join(path);
+    let wav =
+        AppState::try_load_bytes(&file.to_string_lossy(), include_bytes!("../res/557297.wav"));
+    sounds.push((path.into(), wav));
+    Some(wav)
+}
+
+fn run_ovrt_client(
+    stream: TcpStream,
+    sender: &mpsc::SyncSender<Notification>,
+    running: &AtomicBool,
+) -> anyhow::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut socket = tungstenite::accept(stream).map_err(|e| anyhow::anyhow!("{e}"))?;
+    socket
+        .get_ref()
+        .set_read_timeout(Some(Duration::from_millis(200)))?;
+
+    while running.load(Ordering::Relaxed) {
+        let text = match socket.read() {
+            Ok(tungstenite::Message::Text(text)) => text,
+            Ok(tungstenite::Message::Close(_)) => break,
+            Ok(_) => continue,
+            Err(tungstenite::Error::Io(e))
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) =>
+            {
+                continue;
+            }
+            Err(tungstenite::Error::ConnectionClosed) => break,
+            Err(e) => return Err(e.into()),
+        };
+
+        let msg = match serde_json::from_str::<OvrtMessage>(text.as_str()) {
+            Ok(m) => m,
+            Err(e) => {
+                log::error!("Failed to parse OVR Toolkit API message: {e:?}");
+                continue;
+            }
+        };
+
+        let Some(notification) = parse_ovrt(msg) else {
+            continue;
+        };
+
+        if let Err(e) = sender.try_send(notification) {
+            log::error!("Failed to send notification: {e:?}");
+        }
+    }
+    Ok(())
+}
+
+fn parse_ovrt(msg: OvrtMessage) -> Option<Notification> {
+    let wrist = match &*msg.messageType {
+        "SendNotification" => false,
+        "SendWristNotification" => true,
+        other => {
+            log::debug!("Ignoring OVR Toolkit API message: {other}");
+            return None;
+        }
+    };
+
+    // the payload is usually a JSON string, but accept an object too
+    let payload = match msg.json {
+        serde_json::Value::String(s) => serde_json::from_str::<OvrtNotification>(&s),
+        value => serde_json::from_value::<OvrtNotification>(value),
+    };
+    let payload = match payload {
+        Ok(p) => p,
+        Err(e) => {
+            log::error!("Failed to parse OVR Toolkit notification: {e:?}");
+            return None;
+        }
+    };
+
+    let toast = if wrist {
+        // wrist notifications are just a line of text
+        Toast::new(
+            ToastTopic::OvrtWristNotification,
+            payload.body.unwrap_or_else(|| "".into()),
+            "".into(),
+        )
+        .with_timeout(3.)
+    } else {
+        Toast::new(
+            ToastTopic::OvrtNotification,
+            payload.title.unwrap_or_else(|| "".into()),
+            payload.body.unwrap_or_else(|| "".into()),
+        )
+        .with_timeout(5.)
+        .with_sound(true)
+    };
+
+    let toast = match payload.icon.as_deref().filter(|i| !i.is_empty()) {
+        Some(icon) => match IconImage::from_base64_png(icon) {
+            Ok(image) => toast.with_icon(IconSource::Image(image)),
+            Err(e) => {
+                log::debug!("Could not decode notification icon: {e:?}");
+                toast
+            }
+        },
+        None => toast,
+    };
+
+    Some(Notification {
+        app_name: "OVR Toolkit".into(),
+        toast,
+        transient: false,
+    })
+}
+
+fn parse_xso_icon(icon: Option<&str>, base64: Option<bool>) -> Option<IconSource> {
+    let icon = icon.filter(|i| !i.is_empty())?;
+    if base64.unwrap_or(false) {
+        return IconImage::from_base64_png(icon)
+            .map(IconSource::Image)
+            .map_err(|e| log::debug!("Could not decode notification icon: {e:?}"))
+            .ok();
+    }
+    // XSOverlay's built-in icons
+    if matches!(icon, "default" | "error" | "warning") {
+        return None;
+    }
+    // clients are remote, only let them pick from the icon themes
+    if !is_icon_name(icon) {
+        log::warn!("Rejecting XSOverlay icon that is not an icon name: {icon}");
+        return None;
+    }
+    Some(IconSource::Named(icon.into()))
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize)]
+struct OvrtMessage {
+    messageType: Arc<str>,
+    #[serde(default)]
+    json: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct OvrtNotification {
+    title: Option<Arc<str>>,
+    body: Option<Arc<str>>,
+    /// Base64 encoded image
+    icon: Option<Arc<str>>,
+}
+
 #[allow(dead_code)]
 #[allow(non_snake_case)]
 #[derive(Debug, Deserialize)]
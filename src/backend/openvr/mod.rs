@@ -35,7 +35,9 @@ use crate::{
         toast::{Toast, ToastTopic},
         watch::{watch_fade, WATCH_NAME},
     },
+    reminders::on_reminder_due,
     state::AppState,
+    timers::on_timer_finished,
 };
 
 #[cfg(feature = "wayvr")]
@@ -119,7 +121,8 @@ pub fn openvr_run(
     let mut overlays = OverlayContainer::<OpenVrOverlayData>::new(&mut state, headless)?;
     let mut notifications = NotificationManager::new();
     notifications.run_dbus();
-    notifications.run_udp();
+    notifications.run_udp(state.session.config.xso_listen_address.clone());
+    notifications.run_ovrt(state.session.config.ovrt_listen_address.clone());
 
     let mut playspace = playspace::PlayspaceMover::new();
     playspace.playspace_changed(&mut compositor_mgr, &mut chaperone_mgr);
@@ -259,6 +262,21 @@ pub fn openvr_run(
                     SystemTask::ShowHide => {
                         overlays.show_hide(&mut state);
                     }
+                    SystemTask::ReloadPanels => {
+                        overlays.rebuild_panels(&mut state);
+                    }
+                    SystemTask::TimerFinished(id, generation) => {
+                        on_timer_finished(&mut state, &id, generation);
+                    }
+                    SystemTask::ReminderDue(idx, at) => {
+                        on_reminder_due(&mut state, idx, at);
+                    }
+                    SystemTask::NotificationResponse(handle, response) => {
+                        notifications.respond(handle, &response);
+                    }
+                    SystemTask::Macro(run) => {
+                        run.run(&mut state);
+                    }
                 },
                 #[cfg(feature = "wayvr")]
                 TaskType::WayVR(action) => {
@@ -59,12 +59,18 @@ impl OverlayData<OpenVrOverlayData> {
     }
 
     pub(super) fn ensure_image_allocated(&mut self, app: &mut AppState) -> anyhow::Result<bool> {
-        if self.data.image_view.is_some() {
-            return Ok(true);
-        }
         let Some(meta) = self.backend.frame_meta() else {
-            return Ok(false);
+            return Ok(self.data.image_view.is_some());
         };
+        // the renderer may change size, e.g. when the keyboard layout is switched
+        if self
+            .data
+            .image_view
+            .as_ref()
+            .is_some_and(|v| v.image().extent() == meta.extent)
+        {
+            return Ok(true);
+        }
         let image = app.graphics.render_texture(
             meta.extent[0],
             meta.extent[1],
@@ -29,7 +29,9 @@ use crate::{
         toast::{Toast, ToastTopic},
         watch::{watch_fade, WATCH_NAME},
     },
+    reminders::on_reminder_due,
     state::AppState,
+    timers::on_timer_finished,
 };
 
 #[cfg(feature = "wayvr")]
@@ -99,7 +101,8 @@ pub fn openxr_run(
 
     let mut notifications = NotificationManager::new();
     notifications.run_dbus();
-    notifications.run_udp();
+    notifications.run_udp(app.session.config.xso_listen_address.clone());
+    notifications.run_ovrt(app.session.config.ovrt_listen_address.clone());
 
     let mut delete_queue = vec![];
 
@@ -551,6 +554,21 @@ pub fn openxr_run(
                     SystemTask::ShowHide => {
                         overlays.show_hide(&mut app);
                     }
+                    SystemTask::ReloadPanels => {
+                        overlays.rebuild_panels(&mut app);
+                    }
+                    SystemTask::TimerFinished(id, generation) => {
+                        on_timer_finished(&mut app, &id, generation);
+                    }
+                    SystemTask::ReminderDue(idx, at) => {
+                        on_reminder_due(&mut app, idx, at);
+                    }
+                    SystemTask::NotificationResponse(handle, response) => {
+                        notifications.respond(handle, &response);
+                    }
+                    SystemTask::Macro(run) => {
+                        run.run(&mut app);
+                    }
                     _ => {}
                 },
                 #[cfg(feature = "wayvr")]
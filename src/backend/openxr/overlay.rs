@@ -27,11 +27,10 @@ impl OverlayData<OpenXrOverlayData> {
         app: &AppState,
         xr: &'a XrState,
     ) -> anyhow::Result<bool> {
-        if self.data.swapchain.is_some() {
-            return Ok(true);
-        }
-
         let Some(meta) = self.frame_meta() else {
+            if self.data.swapchain.is_some() {
+                return Ok(true);
+            }
             log::warn!(
                 "{}: swapchain cannot be created due to missing metadata",
                 self.state.name
@@ -40,6 +39,15 @@ impl OverlayData<OpenXrOverlayData> {
         };
 
         let extent = meta.extent;
+        // the renderer may change size, e.g. when the keyboard layout is switched
+        if self
+            .data
+            .swapchain
+            .as_ref()
+            .is_some_and(|s| s.extent == extent)
+        {
+            return Ok(true);
+        }
         self.data.swapchain = Some(create_swapchain(
             xr,
             app.graphics.clone(),
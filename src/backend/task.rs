@@ -1,19 +1,23 @@
 use std::{
     cmp,
     collections::{BinaryHeap, VecDeque},
-    sync::atomic::{self, AtomicUsize},
+    sync::{
+        atomic::{self, AtomicUsize},
+        Arc,
+    },
     time::Instant,
 };
 
 use serde::Deserialize;
 
-use crate::state::AppState;
+use crate::{overlays::keyboard::MacroRun, state::AppState};
 
 #[cfg(feature = "wayvr")]
 use crate::gui::modular::button::WayVRAction;
 
 use super::{
     common::OverlaySelector,
+    notifications::NotificationResponse,
     overlay::{OverlayBackend, OverlayState},
 };
 
@@ -50,6 +54,15 @@ pub enum SystemTask {
     ResetPlayspace,
     FixFloor,
     ShowHide,
+    ReloadPanels,
+    /// A countdown is due. Ignored if the timer changed since it was scheduled.
+    TimerFinished(Arc<str>, u64),
+    /// A reminder is due at the given time. Ignored if it was rescheduled since.
+    ReminderDue(usize, chrono::DateTime<chrono::Local>),
+    /// Report an action click or inline reply back to the app that sent a notification
+    NotificationResponse(u64, NotificationResponse),
+    /// Continue a keyboard macro after a WAIT
+    Macro(MacroRun),
 }
 
 pub type OverlayTask = dyn FnOnce(&mut AppState, &mut OverlayState) + Send;
@@ -1,4 +1,10 @@
-use std::sync::Arc;
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
 
 use vulkano::{
     image::{view::ImageView, ImageUsage},
@@ -11,8 +17,9 @@ use vulkano::{
 };
 use winit::{
     dpi::LogicalSize,
-    event::{Event, WindowEvent},
+    event::{ElementState, Event, KeyEvent, WindowEvent},
     event_loop::ControlFlow,
+    keyboard::Key,
     window::Window,
 };
 
@@ -23,6 +30,7 @@ use crate::{
     gui::{
         canvas::Canvas,
         modular::{modular_canvas, ModularData},
+        theme::Theme,
     },
     hid::USE_UINPUT,
     state::{AppState, ScreenMeta},
@@ -33,6 +41,8 @@ use super::{
     overlay::{OverlayID, OverlayRenderer},
 };
 
+const HIT_REGIONS_KEY: &str = "h";
+
 static LAST_SIZE: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
 
 struct PreviewState {
@@ -47,6 +57,7 @@ impl PreviewState {
         surface: Arc<Surface>,
         window: Arc<Window>,
         panel_name: &str,
+        hit_regions: bool,
     ) -> anyhow::Result<Self> {
         let config = load_custom_ui(panel_name)?;
 
@@ -72,6 +83,7 @@ impl PreviewState {
 
         let mut canvas = modular_canvas(config.size, &config.elements, state)?;
         canvas.init(state)?;
+        canvas.set_show_hit_regions(hit_regions);
 
         Ok(Self {
             canvas,
@@ -81,8 +93,27 @@ impl PreviewState {
     }
 }
 
+/// Files that affect how the panel looks: the panel itself and the active theme.
+fn watched_paths(panel_name: &str, state: &AppState) -> Vec<PathBuf> {
+    let root = config_io::get_config_root();
+    vec![
+        root.join(format!("{panel_name}.yaml")),
+        root.join("theme.yaml"),
+        root.join(format!("themes/{}.yaml", state.session.config.theme)),
+    ]
+}
+
+/// Latest modification time among the given files. Missing files are skipped,
+/// since editors often replace the file instead of writing it in place.
+fn last_modified(paths: &[PathBuf]) -> Option<SystemTime> {
+    paths
+        .iter()
+        .filter_map(|p| p.metadata().and_then(|m| m.modified()).ok())
+        .max()
+}
+
 #[allow(clippy::too_many_lines)]
-pub fn uidev_run(panel_name: &str) -> anyhow::Result<()> {
+pub fn uidev_run(panel_name: &str, hit_regions: bool) -> anyhow::Result<()> {
     let (graphics, event_loop, window, surface) = WlxGraphics::new_window()?;
     window.set_resizable(false);
     window.set_title("WlxOverlay UI Preview");
@@ -98,10 +129,12 @@ pub fn uidev_run(panel_name: &str) -> anyhow::Result<()> {
         surface.clone(),
         window.clone(),
         panel_name,
+        hit_regions,
     )?);
 
-    let watch_path = config_io::get_config_root().join(format!("{panel_name}.yaml"));
-    let mut path_last_modified = watch_path.metadata()?.modified()?;
+    let watch_paths = watched_paths(panel_name, &state);
+    let mut path_last_modified = last_modified(&watch_paths);
+    let mut hit_regions = hit_regions;
     let mut recreate = false;
     let mut last_draw = std::time::Instant::now();
 
@@ -122,22 +155,57 @@ pub fn uidev_run(panel_name: &str) -> anyhow::Result<()> {
             } => {
                 recreate = true;
             }
+            Event::WindowEvent {
+                event:
+                    WindowEvent::KeyboardInput {
+                        event:
+                            KeyEvent {
+                                logical_key,
+                                state: ElementState::Pressed,
+                                repeat: false,
+                                ..
+                            },
+                        ..
+                    },
+                ..
+            } => {
+                if logical_key.as_ref() == Key::Character(HIT_REGIONS_KEY) {
+                    hit_regions = !hit_regions;
+                    if let Some(preview) = preview.as_mut() {
+                        preview.canvas.set_show_hit_regions(hit_regions);
+                    }
+                    window.request_redraw();
+                }
+            }
             Event::WindowEvent {
                 event: WindowEvent::RedrawRequested,
                 ..
             } => {
-                let new_modified = watch_path.metadata().unwrap().modified().unwrap();
+                let new_modified = last_modified(&watch_paths);
                 if new_modified > path_last_modified {
+                    log::info!("{panel_name}: change detected, reloading");
+                    state.theme = Theme::load(&state.session.config.theme);
                     recreate = true;
                     path_last_modified = new_modified;
                 }
 
                 if recreate {
-                    drop(preview.take());
-                    preview = Some(
-                        PreviewState::new(&mut state, surface.clone(), window.clone(), panel_name)
-                            .unwrap(),
-                    );
+                    // keep showing the previous version if the new one is broken
+                    match PreviewState::new(
+                        &mut state,
+                        surface.clone(),
+                        window.clone(),
+                        panel_name,
+                        hit_regions,
+                    ) {
+                        Ok(new_preview) => {
+                            drop(preview.take());
+                            preview = Some(new_preview);
+                        }
+                        Err(e) => {
+                            log::error!("{panel_name}: failed to reload: {e:?}");
+                        }
+                    }
                     recreate = false;
                     window.request_redraw();
                 }
@@ -200,6 +268,44 @@ pub fn uidev_run(panel_name: &str) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Renders a panel offscreen and writes it to a PNG file.
+pub fn uidev_export_png(panel_name: &str, path: &Path, hit_regions: bool) -> anyhow::Result<()> {
+    let graphics = WlxGraphics::new_headless()?;
+
+    USE_UINPUT.store(false, std::sync::atomic::Ordering::Relaxed);
+
+    let mut state = AppState::from_graphics(graphics.clone())?;
+    add_dummy_devices(&mut state);
+    add_dummy_screens(&mut state);
+
+    let config = load_custom_ui(panel_name)?;
+    let mut canvas = modular_canvas(config.size, &config.elements, &mut state)?;
+    canvas.init(&mut state)?;
+    canvas.set_show_hit_regions(hit_regions);
+    canvas.should_render(&mut state)?;
+
+    let [width, height] = config.size;
+    let image = graphics.render_texture(width, height, graphics.native_format)?;
+    let tgt = ImageView::new_default(image.clone())?;
+
+    let mut canvas_cmd_buf = CommandBuffers::default();
+    canvas.render(&mut state, tgt, &mut canvas_cmd_buf, 1.0)?;
+    if let Some(future) = canvas_cmd_buf.execute_now(graphics.graphics_queue.clone())? {
+        future.then_signal_fence_and_flush()?.wait(None)?;
+    }
+
+    let pixels = graphics.download_image(image)?;
+
+    let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_srgb(png::SrgbRenderingIntent::Perceptual);
+    encoder.write_header()?.write_image_data(&pixels)?;
+
+    log::info!("{panel_name}: written to {}", path.display());
+    Ok(())
+}
+
 fn create_swapchain(
     graphics: &WlxGraphics,
     surface: Arc<Surface>,
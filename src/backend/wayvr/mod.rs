@@ -106,8 +106,6 @@ impl BlitMethod {
 
 pub struct Config {
     pub click_freeze_time_ms: u32,
-    pub keyboard_repeat_delay_ms: u32,
-    pub keyboard_repeat_rate: u32,
     pub auto_hide_delay: Option<u32>, // if None, auto-hide is disabled
     pub blit_method: BlitMethod,
 }
@@ -230,11 +228,8 @@ impl WayVR {
             },
         );
 
-        let seat_keyboard = seat.add_keyboard(
-            XkbConfig::default(),
-            config.keyboard_repeat_delay_ms as i32,
-            config.keyboard_repeat_rate as i32,
-        )?;
+        // the VR keyboard repeats held keys by itself, so clients must not
+        let seat_keyboard = seat.add_keyboard(XkbConfig::default(), 0, 0)?;
         let seat_pointer = seat.add_pointer();
 
         let tasks = SyncEventQueue::new();
@@ -1,8 +1,12 @@
 use std::path::PathBuf;
 use std::sync::Arc;
 
+use crate::backend::notifications::{DndSchedule, NotificationRuleConfig};
 use crate::config_io;
 use crate::gui::modular::ModularUiConfig;
+use crate::gui::theme::Theme;
+use crate::hid::HidBackend;
+use crate::overlays::keyboard::{KeyboardSplit, UnicodeInput};
 use crate::overlays::toast::DisplayMethod;
 use crate::overlays::toast::ToastTopic;
 use crate::state::LeftRight;
@@ -170,10 +174,38 @@ fn def_toast_topics() -> IdMap<ToastTopic, DisplayMethod> {
     IdMap::new()
 }
 
+fn def_theme() -> Arc<str> {
+    "default".into()
+}
+
 fn def_font() -> Arc<str> {
     "LiberationSans:style=Bold".into()
 }
 
+fn def_icon_theme() -> Arc<str> {
+    "hicolor".into()
+}
+
+fn def_xso_listen_address() -> Arc<str> {
+    "127.0.0.1:42069".into()
+}
+
+const fn def_keyboard_repeat_delay() -> u32 {
+    200
+}
+
+const fn def_keyboard_repeat_rate() -> f32 {
+    50.
+}
+
+const fn def_toast_max_stack() -> usize {
+    3
+}
+
+const fn def_notification_history_size() -> usize {
+    50
+}
+
 const fn def_max_height() -> u16 {
     1440
 }
@@ -207,12 +239,64 @@ pub struct GeneralConfig {
     #[serde(default = "def_empty")]
     pub notification_sound: Arc<str>,
 
+    /// How many toasts to show at once per display method, before queueing the rest
+    #[serde(default = "def_toast_max_stack")]
+    pub toast_max_stack: usize,
+
+    #[serde(default = "def_notification_history_size")]
+    pub notification_history_size: usize,
+
+    /// Per-app overrides for desktop and XSOverlay notifications
+    #[serde(default)]
+    pub notification_rules: Vec<NotificationRuleConfig>,
+
+    /// Turn on do not disturb between these times every day
+    #[serde(default)]
+    pub dnd_schedule: Option<DndSchedule>,
+
+    /// UDP address to receive XSOverlay notifications on. Empty to disable.
+    #[serde(default = "def_xso_listen_address")]
+    pub xso_listen_address: Arc<str>,
+
+    /// Address to accept OVR Toolkit API WebSocket connections on. Empty to disable.
+    #[serde(default = "def_empty")]
+    pub ovrt_listen_address: Arc<str>,
+
+    /// Icon theme to look up notification icons in, before hicolor
+    #[serde(default = "def_icon_theme")]
+    pub notification_icon_theme: Arc<str>,
+
     #[serde(default = "def_true")]
     pub keyboard_sound_enabled: bool,
 
     #[serde(default = "def_one")]
     pub keyboard_scale: f32,
 
+    /// How long a key is held before it starts repeating
+    #[serde(default = "def_keyboard_repeat_delay")]
+    pub keyboard_repeat_delay_ms: u32,
+
+    /// Repeats per second while a key is held. 0 to disable.
+    #[serde(default = "def_keyboard_repeat_rate")]
+    pub keyboard_repeat_rate: f32,
+
+    #[serde(default)]
+    pub unicode_input: UnicodeInput,
+
+    #[serde(default)]
+    pub hid_backend: HidBackend,
+
+    /// Show word suggestions above the keyboard
+    #[serde(default = "def_false")]
+    pub keyboard_prediction: bool,
+
+    /// Remember typed words to suggest them later
+    #[serde(default = "def_false")]
+    pub keyboard_prediction_learn: bool,
+
+    #[serde(default)]
+    pub keyboard_split: KeyboardSplit,
+
     #[serde(default = "def_one")]
     pub desktop_view_scale: f32,
 
@@ -237,6 +321,10 @@ pub struct GeneralConfig {
     #[serde(default = "def_screens")]
     pub show_screens: AStrSet,
 
+    /// Screens that get touch input instead of the mouse
+    #[serde(default = "def_screens")]
+    pub touch_screens: AStrSet,
+
     #[serde(default = "def_curve_values")]
     pub curve_values: AStrMap<f32>,
 
@@ -285,6 +373,9 @@ pub struct GeneralConfig {
     #[serde(default = "def_font")]
     pub primary_font: Arc<str>,
 
+    #[serde(default = "def_theme")]
+    pub theme: Arc<str>,
+
     #[serde(default = "def_one")]
     pub space_drag_multiplier: f32,
 
@@ -339,20 +430,26 @@ impl GeneralConfig {
     }
 }
 
-const FALLBACKS: [&str; 5] = [
+const FALLBACKS: [&str; 8] = [
     include_str!("res/keyboard.yaml"),
     include_str!("res/watch.yaml"),
     include_str!("res/settings.yaml"),
     include_str!("res/anchor.yaml"),
     include_str!("res/wayvr.yaml"),
+    include_str!("res/theme.yaml"),
+    include_str!("res/notifications.yaml"),
+    include_str!("res/emoji.yaml"),
 ];
 
-const FILES: [&str; 5] = [
+const FILES: [&str; 8] = [
     "keyboard.yaml",
     "watch.yaml",
     "settings.yaml",
     "anchor.yaml",
     "wayvr.yaml",
+    "theme.yaml",
+    "notifications.yaml",
+    "emoji.yaml",
 ];
 
 #[derive(Clone, Copy)]
@@ -364,6 +461,9 @@ pub enum ConfigType {
     Anchor,
     #[allow(dead_code)]
     WayVR,
+    Theme,
+    Notifications,
+    Emoji,
 }
 
 pub fn load_known_yaml<T>(config_type: ConfigType) -> T
@@ -395,6 +495,18 @@ pub fn load_custom_ui(name: &str) -> anyhow::Result<ModularUiConfig> {
     Ok(serde_yaml::from_str(&yaml_data)?)
 }
 
+pub fn load_theme(name: &str) -> anyhow::Result<Theme> {
+    // names come from config files and panel actions, keep them inside the themes folder
+    if !config_io::is_contained(name) || name.contains(['/', '\\']) {
+        bail!("Invalid theme name: {name}");
+    }
+    let filename = format!("themes/{name}.yaml");
+    let Some(yaml_data) = config_io::load(&filename) else {
+        bail!("Could not read file at {}", &filename);
+    };
+    Ok(serde_yaml::from_str(&yaml_data)?)
+}
+
 pub fn load_config_with_conf_d<ConfigData>(
     root_config_filename: &str,
     ctype: config_io::ConfigRoot,
@@ -464,6 +576,7 @@ pub struct AutoSettings {
     pub realign_on_showhide: bool,
     pub allow_sliding: bool,
     pub space_drag_multiplier: f32,
+    pub theme: Arc<str>,
 }
 
 fn get_settings_path() -> PathBuf {
@@ -484,6 +597,7 @@ pub fn save_settings(config: &GeneralConfig) -> anyhow::Result<()> {
         realign_on_showhide: config.realign_on_showhide,
         allow_sliding: config.allow_sliding,
         space_drag_multiplier: config.space_drag_multiplier,
+        theme: config.theme.clone(),
     };
 
     let json = serde_json::to_string_pretty(&conf).unwrap(); // want panic
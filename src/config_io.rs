@@ -1,5 +1,8 @@
 use log::error;
-use std::{path::PathBuf, sync::LazyLock};
+use std::{
+    path::{Component, Path, PathBuf},
+    sync::LazyLock,
+};
 
 pub enum ConfigRoot {
     Generic,
@@ -43,6 +46,15 @@ impl ConfigRoot {
     }
 }
 
+/// True for relative paths that stay inside the folder they are joined to:
+/// no root, no `..` and no `.`.
+pub fn is_contained(path: &str) -> bool {
+    !path.is_empty()
+        && Path::new(path)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+}
+
 pub fn get_config_file_path(filename: &str) -> PathBuf {
     get_config_root().join(filename)
 }
@@ -105,14 +105,6 @@ const fn def_autohide_delay() -> u32 {
     750
 }
 
-const fn def_keyboard_repeat_delay() -> u32 {
-    200
-}
-
-const fn def_keyboard_repeat_rate() -> u32 {
-    50
-}
-
 fn def_blit_method() -> String {
     String::from("dmabuf")
 }
@@ -145,14 +137,16 @@ pub struct WayVRConfig {
     #[serde(default = "def_autohide_delay")]
     pub auto_hide_delay: u32,
 
-    #[serde(default = "def_keyboard_repeat_delay")]
-    pub keyboard_repeat_delay: u32,
-
-    #[serde(default = "def_keyboard_repeat_rate")]
-    pub keyboard_repeat_rate: u32,
-
     #[serde(default = "def_blit_method")]
     pub blit_method: String,
+
+    /// Deprecated, moved to `keyboard_repeat_delay_ms` in config.yaml
+    #[serde(default)]
+    pub keyboard_repeat_delay: Option<u32>,
+
+    /// Deprecated, moved to `keyboard_repeat_rate` in config.yaml
+    #[serde(default)]
+    pub keyboard_repeat_rate: Option<u32>,
 }
 
 impl WayVRConfig {
@@ -179,8 +173,6 @@ impl WayVRConfig {
     ) -> anyhow::Result<wayvr::Config> {
         Ok(wayvr::Config {
             click_freeze_time_ms: config_general.click_freeze_time_ms,
-            keyboard_repeat_delay_ms: config_wayvr.keyboard_repeat_delay,
-            keyboard_repeat_rate: config_wayvr.keyboard_repeat_rate,
             blit_method: wayvr::BlitMethod::from_string(&config_wayvr.blit_method)
                 .ok_or_else(|| anyhow::anyhow!("Unknown blit method"))?,
             auto_hide_delay: if config_wayvr.auto_hide {
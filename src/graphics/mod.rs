@@ -677,6 +677,126 @@ impl WlxGraphics {
 
         Ok((Arc::new(me), event_loop, window, surface))
     }
+
+    /// Creates a device without any surface, for rendering panels offscreen.
+    #[cfg(feature = "uidev")]
+    pub fn new_headless() -> anyhow::Result<Arc<Self>> {
+        use vulkano::{
+            descriptor_set::allocator::StandardDescriptorSetAllocatorCreateInfo,
+            instance::InstanceCreateFlags,
+        };
+
+        let instance = Instance::new(
+            get_vulkan_library().clone(),
+            InstanceCreateInfo {
+                flags: InstanceCreateFlags::ENUMERATE_PORTABILITY,
+                enabled_extensions: InstanceExtensions {
+                    khr_get_physical_device_properties2: true,
+                    ..InstanceExtensions::empty()
+                },
+                ..Default::default()
+            },
+        )?;
+
+        // software implementations such as lavapipe are ranked last, but still usable
+        let (physical_device, queue_families) = instance
+            .enumerate_physical_devices()?
+            .filter_map(|p| try_all_queue_families(p.as_ref()).map(|families| (p, families)))
+            .min_by_key(|(p, _)| prio_from_device_type(p))
+            .ok_or_else(|| anyhow!("no suitable physical device found"))?;
+
+        log::info!(
+            "Using vkPhysicalDevice: {}",
+            physical_device.properties().device_name,
+        );
+
+        let (device, queues) = Device::new(
+            physical_device,
+            DeviceCreateInfo {
+                enabled_features: DeviceFeatures {
+                    dynamic_rendering: true,
+                    ..DeviceFeatures::empty()
+                },
+                queue_create_infos: queue_families
+                    .iter()
+                    .map(|fam| QueueCreateInfo {
+                        queue_family_index: fam.queue_family_index,
+                        queues: fam.priorities.clone(),
+                        ..Default::default()
+                    })
+                    .collect::<Vec<_>>(),
+                ..Default::default()
+            },
+        )?;
+
+        let (graphics_queue, transfer_queue, capture_queue) = unwrap_queues(queues.collect());
+
+        let memory_allocator = memory_allocator(device.clone());
+        let command_buffer_allocator = Arc::new(StandardCommandBufferAllocator::new(
+            device.clone(),
+            StandardCommandBufferAllocatorCreateInfo {
+                secondary_buffer_count: 32,
+                ..Default::default()
+            },
+        ));
+        let descriptor_set_allocator = Arc::new(StandardDescriptorSetAllocator::new(
+            device.clone(),
+            StandardDescriptorSetAllocatorCreateInfo::default(),
+        ));
+
+        let (quad_verts, quad_indices) = Self::default_quad(memory_allocator.clone())?;
+        let drm_formats = Self::get_drm_formats(device.clone());
+
+        let me = Self {
+            instance,
+            device,
+            graphics_queue,
+            transfer_queue,
+            capture_queue,
+            memory_allocator,
+            native_format: Format::R8G8B8A8_SRGB,
+            texture_filtering: Filter::Linear,
+            command_buffer_allocator,
+            descriptor_set_allocator,
+            quad_indices,
+            quad_verts,
+            shared_shaders: RwLock::new(HashMap::new()),
+            drm_formats,
+        };
+
+        Ok(Arc::new(me))
+    }
+
+    /// Copies the contents of an R8G8B8A8 image into host memory.
+    #[cfg(feature = "uidev")]
+    pub fn download_image(self: &Arc<Self>, image: Arc<Image>) -> anyhow::Result<Vec<u8>> {
+        use vulkano::command_buffer::CopyImageToBufferInfo;
+
+        let [width, height, _] = image.extent();
+        let buffer = Buffer::new_slice::<u8>(
+            self.memory_allocator.clone(),
+            BufferCreateInfo {
+                usage: BufferUsage::TRANSFER_DST,
+                ..Default::default()
+            },
+            AllocationCreateInfo {
+                memory_type_filter: MemoryTypeFilter::PREFER_HOST
+                    | MemoryTypeFilter::HOST_RANDOM_ACCESS,
+                ..Default::default()
+            },
+            u64::from(width) * u64::from(height) * 4,
+        )?;
+
+        let mut cmd = self.create_command_buffer(CommandBufferUsage::OneTimeSubmit)?;
+        cmd.command_buffer
+            .copy_image_to_buffer(CopyImageToBufferInfo::image_buffer(image, buffer.clone()))?;
+        cmd.build_and_execute()?
+            .then_signal_fence_and_flush()?
+            .wait(None)?;
+
+        let data = buffer.read()?.to_vec();
+        Ok(data)
+    }
     fn default_quad(
         memory_allocator: Arc<StandardMemoryAllocator>,
     ) -> anyhow::Result<(Vert2Buf, IndexBuf)> {
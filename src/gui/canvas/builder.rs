@@ -6,6 +6,7 @@ use vulkano::format::Format;
 use crate::{
     graphics::WlxGraphics,
     gui::{canvas::control::ControlRenderer, GuiColor, KeyCapType},
+    state::AppState,
 };
 
 use super::{control::Control, Canvas, Rect};
@@ -153,6 +154,50 @@ impl<D, S> CanvasBuilder<D, S> {
         &mut self.canvas.controls[idx]
     }
 
+    // Creates a text field with fg_color, bg_color, font_size inherited from the canvas
+    pub fn text_input(
+        &mut self,
+        x: f32,
+        y: f32,
+        w: f32,
+        h: f32,
+        radius: f32,
+        text: Arc<str>,
+    ) -> &mut Control<D, S> {
+        self.list_item(x, y, w, h, radius, text)
+    }
+
+    // Creates a button with left-aligned text, with fg_color, bg_color, font_size inherited from the canvas
+    pub fn list_item(
+        &mut self,
+        x: f32,
+        y: f32,
+        w: f32,
+        h: f32,
+        radius: f32,
+        text: Arc<str>,
+    ) -> &mut Control<D, S> {
+        let idx = self.canvas.controls.len();
+
+        self.canvas.interactive_set_idx(x, y, w, h, idx);
+        self.canvas.controls.push(Control {
+            rect: Rect { x, y, w, h },
+            corner_radius: radius,
+            text,
+            fg_color: self.fg_color,
+            bg_color: self.bg_color,
+            size: self.font_size,
+            on_render_bg: Some(Control::render_rounded_rect),
+            on_render_fg: Some(Control::render_text_left_centered),
+            on_render_hl: Some(Control::render_highlight),
+            ..Control::new()
+        });
+
+        &mut self.canvas.controls[idx]
+    }
+
+    /// Each legend gets its state from `legend_state` by position, and `on_legend_update`.
+    /// `extra` adds a second part to the key, for L-shaped keys. Legends stay in the first part.
     #[allow(clippy::too_many_arguments)]
     pub fn key_button(
         &mut self,
@@ -160,15 +205,22 @@ impl<D, S> CanvasBuilder<D, S> {
         y: f32,
         w: f32,
         h: f32,
+        extra: Option<Rect>,
         radius: f32,
         cap_type: KeyCapType,
         label: &[String],
+        legend_state: impl Fn(usize) -> Option<S>,
+        on_legend_update: Option<fn(&mut Control<D, S>, &mut D, &mut AppState)>,
     ) -> &mut Control<D, S> {
         let idx = self.canvas.controls.len();
         self.canvas.interactive_set_idx(x, y, w, h, idx);
+        if let Some(e) = extra {
+            self.canvas.interactive_set_idx(e.x, e.y, e.w, e.h, idx);
+        }
 
         self.canvas.controls.push(Control {
             rect: Rect { x, y, w, h },
+            extra_rect: extra,
             corner_radius: radius,
             bg_color: self.bg_color,
             on_render_bg: Some(Control::render_rounded_rect),
@@ -253,10 +305,12 @@ impl<D, S> CanvasBuilder<D, S> {
             }
 
             self.canvas.controls.push(Control {
+                state: legend_state(idx),
                 rect,
                 text: Arc::from(label[idx].as_str()),
                 fg_color: self.fg_color * alpha,
                 size: self.font_size,
+                on_update: on_legend_update,
                 on_render_fg: Some(render),
                 ..Control::new()
             });
@@ -3,7 +3,10 @@ use std::sync::Arc;
 use vulkano::image::view::ImageView;
 
 use crate::{
-    backend::input::PointerMode, graphics::WlxCommandBuffer, gui::GuiColor, state::AppState,
+    backend::input::PointerMode,
+    graphics::{WlxCommandBuffer, WlxPass, WlxPipeline},
+    gui::GuiColor,
+    state::AppState,
 };
 
 use super::{CanvasData, Rect};
@@ -23,6 +26,8 @@ pub type ControlRendererHl<D, S> = fn(
 pub struct Control<D, S> {
     pub state: Option<S>,
     pub rect: Rect,
+    /// Second part of an L-shaped control, drawn and highlighted along with `rect`
+    pub extra_rect: Option<Rect>,
     pub corner_radius: f32,
     pub fg_color: GuiColor,
     pub bg_color: GuiColor,
@@ -53,6 +58,7 @@ impl<D, S> Control<D, S> {
                 w: 0.,
                 h: 0.,
             },
+            extra_rect: None,
             corner_radius: 0.,
             fg_color: Vec4::ONE,
             bg_color: Vec4::ZERO,
@@ -103,50 +109,26 @@ impl<D, S> Control<D, S> {
         self.fg_dirty = true;
     }
 
+    pub fn set_bg_color(&mut self, color: GuiColor) {
+        if self.bg_color == color {
+            return;
+        }
+        self.bg_color = color;
+        self.bg_dirty = true;
+    }
+
     pub fn render_rounded_rect(
         &self,
         canvas: &CanvasData<D>,
         _: &mut AppState,
         cmd_buffer: &mut WlxCommandBuffer,
     ) -> anyhow::Result<()> {
-        let pass = {
-            let vertex_buffer = canvas.graphics.upload_verts(
-                canvas.width as _,
-                canvas.height as _,
-                self.rect.x,
-                self.rect.y,
-                self.rect.w,
-                self.rect.h,
-            )?;
-
-            let clamped_radius = self
-                .corner_radius
-                .min(self.rect.w / 2.0)
-                .min(self.rect.h / 2.0);
-
-            let skew_radius = [clamped_radius / self.rect.w, clamped_radius / self.rect.h];
-
-            let set0 = canvas.pipeline_bg_color.uniform_buffer(
-                0,
-                vec![
-                    self.bg_color.x,
-                    self.bg_color.y,
-                    self.bg_color.z,
-                    self.bg_color.w,
-                    skew_radius[0],
-                    skew_radius[1],
-                ],
-            )?;
-
-            canvas.pipeline_bg_color.create_pass(
-                [canvas.width as _, canvas.height as _],
-                vertex_buffer,
-                canvas.graphics.quad_indices.clone(),
-                vec![set0],
-            )?
-        };
-
-        cmd_buffer.run_ref(&pass)
+        for rect in std::iter::once(&self.rect).chain(self.extra_rect.as_ref()) {
+            let pass =
+                self.rounded_rect_pass(canvas, &canvas.pipeline_bg_color, rect, self.bg_color)?;
+            cmd_buffer.run_ref(&pass)?;
+        }
+        Ok(())
     }
 
     pub(super) fn render_highlight(
@@ -156,23 +138,34 @@ impl<D, S> Control<D, S> {
         cmd_buffer: &mut WlxCommandBuffer,
         color: GuiColor,
     ) -> anyhow::Result<()> {
+        for rect in std::iter::once(&self.rect).chain(self.extra_rect.as_ref()) {
+            let pass = self.rounded_rect_pass(canvas, &canvas.pipeline_hl_color, rect, color)?;
+            cmd_buffer.run_ref(&pass)?;
+        }
+        Ok(())
+    }
+
+    fn rounded_rect_pass(
+        &self,
+        canvas: &CanvasData<D>,
+        pipeline: &Arc<WlxPipeline>,
+        rect: &Rect,
+        color: GuiColor,
+    ) -> anyhow::Result<WlxPass> {
         let vertex_buffer = canvas.graphics.upload_verts(
             canvas.width as _,
             canvas.height as _,
-            self.rect.x,
-            self.rect.y,
-            self.rect.w,
-            self.rect.h,
+            rect.x,
+            rect.y,
+            rect.w,
+            rect.h,
         )?;
 
-        let clamped_radius = self
-            .corner_radius
-            .min(self.rect.w / 2.0)
-            .min(self.rect.h / 2.0);
+        let clamped_radius = self.corner_radius.min(rect.w / 2.0).min(rect.h / 2.0);
 
-        let skew_radius = [clamped_radius / self.rect.w, clamped_radius / self.rect.h];
+        let skew_radius = [clamped_radius / rect.w, clamped_radius / rect.h];
 
-        let set0 = canvas.pipeline_hl_color.uniform_buffer(
+        let set0 = pipeline.uniform_buffer(
             0,
             vec![
                 color.x,
@@ -184,14 +177,12 @@ impl<D, S> Control<D, S> {
             ],
         )?;
 
-        let pass = canvas.pipeline_hl_color.create_pass(
+        pipeline.create_pass(
             [canvas.width as _, canvas.height as _],
             vertex_buffer,
             canvas.graphics.quad_indices.clone(),
             vec![set0],
-        )?;
-
-        cmd_buffer.run_ref(&pass)
+        )
     }
 
     pub(super) fn render_text(
@@ -249,10 +240,37 @@ impl<D, S> Control<D, S> {
             .fc
             .get_text_size(&self.text, self.size, canvas.graphics.clone())?;
 
+        let x = w.mul_add(-0.5, self.rect.w.mul_add(0.5, self.rect.x));
+        self.render_text_lines(canvas, app, cmd_buffer, x, h)
+    }
+
+    pub(super) fn render_text_left_centered(
+        &self,
+        canvas: &CanvasData<D>,
+        app: &mut AppState,
+        cmd_buffer: &mut WlxCommandBuffer,
+    ) -> anyhow::Result<()> {
+        let (_, h) = app
+            .fc
+            .get_text_size(&self.text, self.size, canvas.graphics.clone())?;
+
+        let x = self.rect.x + self.corner_radius.max(8.);
+        self.render_text_lines(canvas, app, cmd_buffer, x, h)
+    }
+
+    /// Draws each line of the text starting at `x`, the block of height `h` centered vertically
+    fn render_text_lines(
+        &self,
+        canvas: &CanvasData<D>,
+        app: &mut AppState,
+        cmd_buffer: &mut WlxCommandBuffer,
+        x: f32,
+        h: f32,
+    ) -> anyhow::Result<()> {
         let mut cur_y =
             (self.size as f32).mul_add(-0.25, h.mul_add(-0.5, self.rect.y + (self.rect.h)));
         for line in self.text.lines() {
-            let mut cur_x = w.mul_add(-0.5, self.rect.w.mul_add(0.5, self.rect.x));
+            let mut cur_x = x;
             for glyph in app
                 .fc
                 .get_glyphs(line, self.size, canvas.graphics.clone())?
@@ -1,7 +1,7 @@
 pub mod builder;
 pub mod control;
 
-use std::sync::Arc;
+use std::{rc::Rc, sync::Arc};
 
 use glam::{Vec2, Vec4};
 use vulkano::{command_buffer::CommandBufferUsage, format::Format, image::view::ImageView};
@@ -11,12 +11,22 @@ use crate::{
         input::{Haptics, InteractionHandler, PointerHit},
         overlay::{FrameMeta, OverlayBackend, OverlayRenderer, ShouldRender},
     },
-    graphics::{CommandBuffers, WlxGraphics, WlxPipeline, BLEND_ALPHA},
+    graphics::{CommandBuffers, WlxCommandBuffer, WlxGraphics, WlxPipeline, BLEND_ALPHA},
     state::AppState,
 };
 
 const RES_DIVIDER: usize = 4;
 
+const HIT_REGION_COLORS: [Vec4; 6] = [
+    Vec4::new(1., 0.2, 0.2, 0.35),
+    Vec4::new(0.2, 1., 0.2, 0.35),
+    Vec4::new(0.2, 0.4, 1., 0.35),
+    Vec4::new(1., 1., 0.2, 0.35),
+    Vec4::new(1., 0.2, 1., 0.35),
+    Vec4::new(0.2, 1., 1., 0.35),
+];
+
+#[derive(Clone, Copy)]
 pub struct Rect {
     x: f32,
     y: f32,
@@ -24,6 +34,12 @@ pub struct Rect {
     h: f32,
 }
 
+impl Rect {
+    pub const fn new(x: f32, y: f32, w: f32, h: f32) -> Self {
+        Self { x, y, w, h }
+    }
+}
+
 pub struct CanvasData<D> {
     pub data: D,
     pub width: usize,
@@ -59,6 +75,11 @@ pub struct Canvas<D, S> {
     back_dirty: bool,
     high_dirty: bool,
     fore_dirty: bool,
+
+    show_hit_regions: bool,
+
+    /// Identifies this canvas as the owner of a text focus given out by its controls
+    text_focus_owner: Rc<()>,
 }
 
 impl<D, S> Canvas<D, S> {
@@ -151,6 +172,8 @@ impl<D, S> Canvas<D, S> {
             back_dirty: false,
             high_dirty: false,
             fore_dirty: false,
+            show_hit_regions: false,
+            text_focus_owner: Rc::new(()),
         })
     }
 
@@ -180,6 +203,103 @@ impl<D, S> Canvas<D, S> {
     pub const fn data_mut(&mut self) -> &mut D {
         &mut self.data.data
     }
+
+    /// Runs the release handlers of all controls that are still held down.
+    pub fn release_all(&mut self, app: &mut AppState) {
+        for pointer in 0..self.pressed_controls.len() {
+            let Some(idx) = self.pressed_controls[pointer].take() else {
+                continue;
+            };
+            let c = &mut self.controls[idx];
+            if let Some(ref mut f) = c.on_release {
+                f(c, &mut self.data.data, app);
+            }
+        }
+    }
+
+    /// Draw the pointer hit-test regions on top of the canvas, for debugging.
+    pub const fn set_show_hit_regions(&mut self, show: bool) {
+        self.show_hit_regions = show;
+        self.high_dirty = true;
+    }
+
+    /// Returns the hit-test map as `(control_idx, x, y, w, h)` rectangles in pixels.
+    /// Horizontal runs of cells are merged with identical runs on the rows below.
+    fn hit_regions(&self) -> Vec<(usize, f32, f32, f32, f32)> {
+        let mut done = Vec::new();
+        // (idx, x_start, x_end, y_start, y_end) in cells
+        let mut open: Vec<(usize, usize, usize, usize, usize)> = Vec::new();
+
+        for y in 0..self.interact_rows {
+            let row = &self.interact_map[y * self.interact_stride..(y + 1) * self.interact_stride];
+            let mut runs = Vec::new();
+            let mut x = 0;
+            while x < row.len() {
+                let Some(idx) = row[x] else {
+                    x += 1;
+                    continue;
+                };
+                let start = x;
+                while x < row.len() && row[x] == Some(idx) {
+                    x += 1;
+                }
+                runs.push((idx as usize, start, x));
+            }
+
+            let mut next_open = Vec::with_capacity(runs.len());
+            for (idx, x0, x1) in runs {
+                if let Some(pos) = open
+                    .iter()
+                    .position(|r| r.0 == idx && r.1 == x0 && r.2 == x1 && r.4 == y)
+                {
+                    let mut run = open.swap_remove(pos);
+                    run.4 = y + 1;
+                    next_open.push(run);
+                } else {
+                    next_open.push((idx, x0, x1, y, y + 1));
+                }
+            }
+            done.append(&mut open);
+            open = next_open;
+        }
+        done.append(&mut open);
+
+        done.into_iter()
+            .map(|(idx, x0, x1, y0, y1)| {
+                (
+                    idx,
+                    (x0 * RES_DIVIDER) as f32,
+                    (y0 * RES_DIVIDER) as f32,
+                    ((x1 - x0) * RES_DIVIDER) as f32,
+                    ((y1 - y0) * RES_DIVIDER) as f32,
+                )
+            })
+            .collect()
+    }
+
+    fn render_hit_regions(&self, cmd_buffer: &mut WlxCommandBuffer) -> anyhow::Result<()> {
+        let canvas = &self.data;
+        for (idx, x, y, w, h) in self.hit_regions() {
+            let color = HIT_REGION_COLORS[idx % HIT_REGION_COLORS.len()];
+            let vertex_buffer =
+                canvas
+                    .graphics
+                    .upload_verts(canvas.width as _, canvas.height as _, x, y, w, h)?;
+
+            let set0 = canvas
+                .pipeline_hl_color
+                .uniform_buffer(0, vec![color.x, color.y, color.z, color.w, 0., 0.])?;
+
+            let pass = canvas.pipeline_hl_color.create_pass(
+                [canvas.width as _, canvas.height as _],
+                vertex_buffer,
+                canvas.graphics.quad_indices.clone(),
+                vec![set0],
+            )?;
+            cmd_buffer.run_ref(&pass)?;
+        }
+        Ok(())
+    }
 }
 
 impl<D, S> InteractionHandler for Canvas<D, S> {
@@ -221,6 +341,9 @@ impl<D, S> InteractionHandler for Canvas<D, S> {
                 if let Some(ref mut f) = c.on_press {
                     self.pressed_controls[hit.pointer] = Some(idx);
                     f(c, &mut self.data.data, app, hit.mode);
+                    if let Some(focus) = app.text_focus.as_mut() {
+                        focus.claim(&self.text_focus_owner);
+                    }
                 }
             } else if let Some(ref mut f) = c.on_release {
                 self.pressed_controls[hit.pointer] = None;
@@ -244,7 +367,14 @@ impl<D, S> OverlayRenderer for Canvas<D, S> {
     fn init(&mut self, _app: &mut AppState) -> anyhow::Result<()> {
         Ok(())
     }
-    fn pause(&mut self, _app: &mut AppState) -> anyhow::Result<()> {
+    fn pause(&mut self, app: &mut AppState) -> anyhow::Result<()> {
+        if app
+            .text_focus
+            .as_ref()
+            .is_some_and(|f| f.is_owned_by(&self.text_focus_owner))
+        {
+            app.text_focus = None;
+        }
         Ok(())
     }
     fn resume(&mut self, _app: &mut AppState) -> anyhow::Result<()> {
@@ -351,6 +481,10 @@ impl<D, S> OverlayRenderer for Canvas<D, S> {
         // mostly static text
         cmd_buffer.run_ref(&pass_fore)?;
 
+        if self.show_hit_regions {
+            self.render_hit_regions(&mut cmd_buffer)?;
+        }
+
         cmd_buffer.end_rendering()?;
         buf.push(cmd_buffer.build()?);
         Ok(true)
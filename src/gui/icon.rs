@@ -0,0 +1,199 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use base64::Engine;
+use vulkano::{command_buffer::CommandBufferUsage, format::Format, image::view::ImageView};
+
+use crate::{config_io, state::AppState};
+
+/// Sizes to try when looking up themed icons, best first
+const THEME_ICON_SIZES: [&str; 6] = ["64x64", "48x48", "96x96", "128x128", "256x256", "32x32"];
+
+/// Decoded RGBA8 image
+pub struct IconImage {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+/// An icon as received, decoded or resolved when it is shown.
+pub enum IconSource {
+    Image(IconImage),
+    /// Freedesktop icon name, looked up in the icon themes
+    Named(Arc<str>),
+    /// Absolute file path. Only for senders on the local machine.
+    File(PathBuf),
+}
+
+impl IconImage {
+    pub fn from_png(bytes: &[u8]) -> anyhow::Result<Self> {
+        let mut decoder = png::Decoder::new(bytes);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info()?;
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf)?;
+        buf.truncate(info.buffer_size());
+
+        let channels = match info.color_type {
+            png::ColorType::Grayscale => 1,
+            png::ColorType::GrayscaleAlpha => 2,
+            png::ColorType::Rgb => 3,
+            png::ColorType::Rgba => 4,
+            png::ColorType::Indexed => anyhow::bail!("Indexed PNG was not expanded"),
+        };
+
+        Self::from_pixels(
+            info.width,
+            info.height,
+            info.width as usize * channels,
+            channels,
+            &buf,
+        )
+    }
+
+    pub fn from_base64_png(data: &str) -> anyhow::Result<Self> {
+        // some senders include a data URI prefix
+        let data = data.rsplit_once(',').map_or(data, |(_, d)| d);
+        let bytes = base64::engine::general_purpose::STANDARD.decode(data.trim())?;
+        Self::from_png(&bytes)
+    }
+
+    /// Converts 8-bit gray, gray+alpha, RGB or RGBA rows to RGBA.
+    pub fn from_pixels(
+        width: u32,
+        height: u32,
+        rowstride: usize,
+        channels: usize,
+        data: &[u8],
+    ) -> anyhow::Result<Self> {
+        if width == 0 || height == 0 || !(1..=4).contains(&channels) {
+            anyhow::bail!("Unsupported image: {width}x{height} with {channels} channels");
+        }
+        let row_len = width as usize * channels;
+        if rowstride < row_len || data.len() < rowstride * (height as usize - 1) + row_len {
+            anyhow::bail!("Image data too short for {width}x{height}");
+        }
+
+        let mut rgba = Vec::with_capacity(width as usize * height as usize * 4);
+        for row in data.chunks(rowstride).take(height as usize) {
+            for px in row[..row_len].chunks_exact(channels) {
+                match *px {
+                    [v] => rgba.extend_from_slice(&[v, v, v, 255]),
+                    [v, a] => rgba.extend_from_slice(&[v, v, v, a]),
+                    [r, g, b] => rgba.extend_from_slice(&[r, g, b, 255]),
+                    [r, g, b, a] => rgba.extend_from_slice(&[r, g, b, a]),
+                    _ => unreachable!(),
+                }
+            }
+        }
+
+        Ok(Self {
+            width,
+            height,
+            rgba,
+        })
+    }
+
+    pub fn upload(&self, app: &AppState) -> anyhow::Result<Arc<ImageView>> {
+        let mut command_buffer = app.graphics.create_uploads_command_buffer(
+            app.graphics.transfer_queue.clone(),
+            CommandBufferUsage::OneTimeSubmit,
+        )?;
+        let image = command_buffer.texture2d_raw(
+            self.width,
+            self.height,
+            Format::R8G8B8A8_SRGB,
+            &self.rgba,
+        )?;
+        command_buffer.build_and_execute_now()?;
+        Ok(ImageView::new_default(image)?)
+    }
+}
+
+impl IconSource {
+    /// Reads an icon name, absolute path or `file://` URI.
+    pub fn from_name_or_path(name: &str) -> Self {
+        let path = Path::new(name.strip_prefix("file://").unwrap_or(name));
+        if path.is_absolute() {
+            Self::File(path.to_path_buf())
+        } else {
+            Self::Named(name.into())
+        }
+    }
+
+    pub fn load(self, icon_theme: &str) -> anyhow::Result<IconImage> {
+        let path = match self {
+            Self::Image(image) => return Ok(image),
+            Self::Named(name) => find_icon(&name, icon_theme),
+            Self::File(path) => path.is_file().then_some(path),
+        };
+        let Some(path) = path else {
+            anyhow::bail!("Icon not found");
+        };
+        IconImage::from_png(&std::fs::read(path)?)
+    }
+}
+
+/// True for names that cannot leave the icon folders they are looked up in.
+pub fn is_icon_name(name: &str) -> bool {
+    config_io::is_contained(name) && !name.contains(['/', '\\'])
+}
+
+/// Resolves an icon name in the icon themes. Only PNG icons are supported.
+fn find_icon(name: &str, icon_theme: &str) -> Option<PathBuf> {
+    if !is_icon_name(name) {
+        return None;
+    }
+
+    let xdg_dirs = xdg::BaseDirectories::new().ok()?;
+    let mut themes = vec![icon_theme];
+    if icon_theme != "hicolor" {
+        themes.push("hicolor");
+    }
+
+    themes
+        .iter()
+        .flat_map(|theme| {
+            THEME_ICON_SIZES
+                .iter()
+                .map(move |size| format!("icons/{theme}/{size}/apps/{name}.png"))
+        })
+        .chain(std::iter::once(format!("pixmaps/{name}.png")))
+        .find_map(|file| xdg_dirs.find_data_file(file))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn icon_names_stay_in_icon_folders() {
+        assert!(is_icon_name("firefox"));
+        assert!(is_icon_name("org.kde.dolphin"));
+        assert!(!is_icon_name(""));
+        assert!(!is_icon_name(".."));
+        assert!(!is_icon_name("../../../etc/passwd"));
+        assert!(!is_icon_name("/etc/passwd"));
+        assert!(!is_icon_name("apps/firefox"));
+        assert!(!is_icon_name("..\\secret"));
+    }
+
+    #[test]
+    fn paths_are_told_apart_from_names() {
+        assert!(matches!(
+            IconSource::from_name_or_path("/tmp/icon.png"),
+            IconSource::File(p) if p == Path::new("/tmp/icon.png")
+        ));
+        assert!(matches!(
+            IconSource::from_name_or_path("file:///tmp/icon.png"),
+            IconSource::File(p) if p == Path::new("/tmp/icon.png")
+        ));
+        assert!(matches!(
+            IconSource::from_name_or_path("firefox"),
+            IconSource::Named(n) if &*n == "firefox"
+        ));
+        assert!(find_icon("../../etc/passwd", "hicolor").is_none());
+    }
+}
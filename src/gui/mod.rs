@@ -4,7 +4,9 @@ use glam::Vec4;
 
 pub mod canvas;
 pub mod font;
+pub mod icon;
 pub mod modular;
+pub mod theme;
 
 pub type GuiColor = Vec4;
 pub static FALLBACK_COLOR: LazyLock<GuiColor> = LazyLock::new(|| Vec4::new(1., 0., 1., 1.));
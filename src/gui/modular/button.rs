@@ -7,22 +7,27 @@ use std::{
 };
 
 use glam::{Quat, Vec4};
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
 
 use crate::{
     backend::{
         common::OverlaySelector,
         input::PointerMode,
+        notifications::{set_dnd, DndSource, NotificationResponse},
         overlay::Positioning,
         task::{ColorChannel, SystemTask, TaskType},
     },
     config::{save_layout, save_settings, AStrSetExt},
+    gui::theme::Theme,
     hid::VirtualKey,
     overlays::{
-        toast::{error_toast, Toast, ToastTopic},
+        keyboard::{parse_macro, type_text, MacroRun, MacroVerb},
+        toast::{dismiss_toast, error_toast, Toast, ToastTopic},
         watch::WATCH_NAME,
     },
+    reminders::{run_reminder, ReminderAction},
     state::AppState,
+    timers::{run_timer, TimerAction},
 };
 
 #[cfg(any(not(feature = "wayvr"), not(feature = "osc")))]
@@ -60,6 +65,7 @@ pub enum HighlightTest {
     AutoRealign,
     NotificationSounds,
     Notifications,
+    DoNotDisturb,
     RorateLock,
 }
 
@@ -69,12 +75,18 @@ pub enum SystemAction {
     ToggleAutoRealign,
     ToggleNotificationSounds,
     ToggleNotifications,
+    /// Hold back notifications until turned off again
+    ToggleDoNotDisturb,
     ToggleRotateLock,
     PlayspaceResetOffset,
     PlayspaceFixFloor,
     RecalculateExtent,
     PersistConfig,
     PersistLayout,
+    /// Switch to the theme at `themes/<name>.yaml`, or "default"
+    SwitchTheme {
+        name: Arc<str>,
+    },
 }
 
 #[derive(Deserialize, Clone)]
@@ -122,6 +134,14 @@ pub enum WindowAction {
     Destroy,
 }
 
+#[derive(Deserialize, Clone)]
+pub enum NotificationAction {
+    /// Remove all entries from the notification history
+    ClearHistory,
+    /// Click an action button of a desktop notification
+    Invoke { notification: u64, key: Arc<str> },
+}
+
 #[derive(Deserialize, Clone)]
 pub enum WayVRDisplayClickAction {
     ToggleVisibility,
@@ -153,6 +173,14 @@ pub enum ButtonAction {
         keycode: VirtualKey,
         action: PressRelease,
     },
+    TypeText {
+        text: Arc<str>,
+    },
+    /// Same syntax as `macros` in keyboard.yaml
+    Macro {
+        #[serde(deserialize_with = "deserialize_macro")]
+        verbs: Arc<[MacroVerb]>,
+    },
     Watch {
         action: WatchAction,
     },
@@ -180,6 +208,22 @@ pub enum ButtonAction {
     DragMultiplier {
         delta: f32,
     },
+    Timer {
+        id: Arc<str>,
+        action: TimerAction,
+    },
+    /// Acts on the reminder that was shown last
+    Reminder {
+        action: ReminderAction,
+    },
+    Notifications {
+        action: NotificationAction,
+    },
+    /// Closes a toast. Used by toast buttons.
+    #[serde(skip)]
+    DismissToast {
+        key: u64,
+    },
     System {
         action: SystemAction,
     },
@@ -189,6 +233,79 @@ pub enum ButtonAction {
     },
 }
 
+const TEXT_PLACEHOLDER: &str = "{text}";
+
+/// Parses macros once when the panel is loaded, so that mistakes are reported right away.
+fn deserialize_macro<'de, D>(deserializer: D) -> Result<Arc<[MacroVerb]>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let verbs = Vec::<Arc<str>>::deserialize(deserializer)?;
+    Ok(parse_macro(&verbs).into())
+}
+
+impl ButtonAction {
+    /// Returns a copy of this action with `{text}` replaced in its string arguments.
+    pub fn with_text(&self, text: &str) -> Self {
+        let sub = |s: &Arc<str>| -> Arc<str> { s.replace(TEXT_PLACEHOLDER, text).into() };
+        match self {
+            Self::Exec { command, toast } => Self::Exec {
+                command: command.iter().map(sub).collect(),
+                toast: toast.as_ref().map(sub),
+            },
+            Self::TypeText { text: typed } => Self::TypeText { text: sub(typed) },
+            Self::Macro { verbs } => Self::Macro {
+                verbs: verbs
+                    .iter()
+                    .map(|v| match v {
+                        MacroVerb::Type(typed) => {
+                            MacroVerb::Type(typed.replace(TEXT_PLACEHOLDER, text))
+                        }
+                        v => v.clone(),
+                    })
+                    .collect(),
+            },
+            Self::Toast {
+                message,
+                body,
+                seconds,
+            } => Self::Toast {
+                message: sub(message),
+                body: body.as_ref().map(sub),
+                seconds: *seconds,
+            },
+            Self::WayVR {
+                action:
+                    WayVRAction::AppClick {
+                        catalog_name,
+                        app_name,
+                    },
+            } => Self::WayVR {
+                action: WayVRAction::AppClick {
+                    catalog_name: sub(catalog_name),
+                    app_name: sub(app_name),
+                },
+            },
+            #[cfg(feature = "osc")]
+            Self::SendOscValue { parameter, values } => Self::SendOscValue {
+                parameter: parameter.clone(),
+                values: values.as_ref().map(|values| {
+                    values
+                        .iter()
+                        .map(|v| match v {
+                            OscValue::String { value } => OscValue::String {
+                                value: value.replace(TEXT_PLACEHOLDER, text),
+                            },
+                            v => v.clone(),
+                        })
+                        .collect()
+                }),
+            },
+            action => action.clone(),
+        }
+    }
+}
+
 #[derive(Deserialize, Clone)]
 #[serde(tag = "type")]
 #[cfg(feature = "osc")]
@@ -248,6 +365,16 @@ pub struct ButtonData {
     pub(super) highlight: Option<HighlightTest>,
 }
 
+impl ButtonData {
+    /// Button that runs the given actions when clicked
+    pub fn on_click(actions: Vec<ButtonAction>) -> Self {
+        Self {
+            click_up: Some(actions),
+            ..Default::default()
+        }
+    }
+}
+
 pub fn modular_button_init(button: &mut ModularControl, data: &ButtonData) {
     button.state = Some(ModularData::Button(Box::new(data.clone())));
     button.on_press = Some(modular_button_dn);
@@ -367,6 +494,7 @@ fn modular_button_highlight(
             HighlightTest::AutoRealign => app.session.config.realign_on_showhide,
             HighlightTest::NotificationSounds => app.session.config.notifications_sound_enabled,
             HighlightTest::Notifications => app.session.config.notifications_enabled,
+            HighlightTest::DoNotDisturb => app.dnd.enabled,
             HighlightTest::RorateLock => !app.session.config.space_rotate_unlocked,
         };
 
@@ -377,7 +505,7 @@ fn modular_button_highlight(
     None
 }
 
-fn handle_action(action: &ButtonAction, press: &mut PressData, app: &mut AppState) {
+pub(super) fn handle_action(action: &ButtonAction, press: &mut PressData, app: &mut AppState) {
     match action {
         ButtonAction::Exec { command, toast } => run_exec(command, toast.clone(), press, app),
         ButtonAction::Watch { action } => run_watch(action, app),
@@ -397,6 +525,8 @@ fn handle_action(action: &ButtonAction, press: &mut PressData, app: &mut AppStat
         ButtonAction::VirtualKey { keycode, action } => app
             .hid_provider
             .send_key(*keycode, matches!(*action, PressRelease::Press)),
+        ButtonAction::TypeText { text } => type_text(app, text),
+        ButtonAction::Macro { verbs } => MacroRun::new(verbs.clone(), 0).run(app),
         ButtonAction::Toast {
             message,
             body,
@@ -417,6 +547,10 @@ fn handle_action(action: &ButtonAction, press: &mut PressData, app: &mut AppStat
                 .enqueue(TaskType::System(SystemTask::ColorGain(channel, delta)));
         }
         ButtonAction::System { action } => run_system(action, app),
+        ButtonAction::Timer { id, action } => run_timer(id, action, app),
+        ButtonAction::Reminder { action } => run_reminder(action, app),
+        ButtonAction::Notifications { action } => run_notification_action(action, app),
+        ButtonAction::DismissToast { key } => dismiss_toast(app, *key),
         ButtonAction::DragMultiplier { delta } => {
             app.session.config.space_drag_multiplier += delta;
         }
@@ -519,6 +653,9 @@ fn run_system(action: &SystemAction, app: &mut AppState) {
         SystemAction::RecalculateExtent => {
             todo!()
         }
+        SystemAction::ToggleDoNotDisturb => {
+            set_dnd(app, !app.dnd.enabled, DndSource::User);
+        }
         SystemAction::ToggleNotifications => {
             app.session.config.notifications_enabled = !app.session.config.notifications_enabled;
             Toast::new(
@@ -556,6 +693,12 @@ fn run_system(action: &SystemAction, app: &mut AppState) {
                 error_toast(app, "Failed to save layout", e);
             }
         }
+        SystemAction::SwitchTheme { name } => {
+            app.theme = Theme::load(name);
+            app.session.config.theme = name.clone();
+            app.tasks
+                .enqueue(TaskType::System(SystemTask::ReloadPanels));
+        }
     }
 }
 
@@ -860,6 +1003,23 @@ fn run_window(window: &Arc<str>, action: &WindowAction, app: &mut AppState) {
     }
 }
 
+fn run_notification_action(action: &NotificationAction, app: &mut AppState) {
+    let (notification, response) = match action {
+        NotificationAction::ClearHistory => {
+            app.notification_history.clear();
+            return;
+        }
+        NotificationAction::Invoke { notification, key } => {
+            (*notification, NotificationResponse::Action(key.clone()))
+        }
+    };
+    app.tasks
+        .enqueue(TaskType::System(SystemTask::NotificationResponse(
+            notification,
+            response,
+        )));
+}
+
 const THUMP_AUDIO_WAV: &[u8] = include_bytes!("../../res/380885.wav");
 
 fn audio_thump(app: &mut AppState) {
@@ -6,18 +6,18 @@ use std::{
     io::Read,
     process::{self, Stdio},
     sync::Arc,
-    time::Instant,
+    time::{Duration, Instant},
 };
 
 use crate::{
-    gui::modular::FALLBACK_COLOR,
+    gui::{modular::FALLBACK_COLOR, GuiColor},
     overlays::toast::{error_toast, error_toast_str},
     state::AppState,
 };
 
 use serde::Deserialize;
 
-use super::{color_parse_or_default, ExecArgs, GuiColor, ModularControl, ModularData};
+use super::{ExecArgs, ModularControl, ModularData};
 
 #[derive(Deserialize)]
 #[serde(untagged)]
@@ -43,8 +43,10 @@ pub enum LabelContent {
     Timezone {
         timezone: usize,
     },
+    /// Time since panel creation, or the named timer if `id` is set
     Timer {
         format: Arc<str>,
+        id: Option<Arc<str>>,
     },
     Battery {
         device: usize,
@@ -54,6 +56,8 @@ pub enum LabelContent {
     },
     DragMultiplier,
     Ipd,
+    /// The reminder that is due, or the next upcoming one
+    NextReminder,
 }
 
 pub enum LabelData {
@@ -72,6 +76,10 @@ pub enum LabelData {
         format: Arc<str>,
         start: Instant,
     },
+    NamedTimer {
+        format: Arc<str>,
+        id: Arc<str>,
+    },
     Exec {
         last_exec: Instant,
         interval: f32,
@@ -82,6 +90,7 @@ pub enum LabelData {
         last_ipd: f32,
     },
     DragMultiplier,
+    NextReminder,
 }
 
 pub fn modular_label_init(label: &mut ModularControl, content: &LabelContent, app: &AppState) {
@@ -95,8 +104,8 @@ pub fn modular_label_init(label: &mut ModularControl, content: &LabelContent, ap
             device: *device,
             low_threshold: *low_threshold,
             normal_color: label.fg_color,
-            low_color: color_parse_or_default(low_color),
-            charging_color: color_parse_or_default(charging_color),
+            low_color: app.theme.color_or_default(low_color),
+            charging_color: app.theme.color_or_default(charging_color),
         }),
         LabelContent::Clock { format, timezone } => {
             let tz_str = match timezone {
@@ -140,10 +149,17 @@ pub fn modular_label_init(label: &mut ModularControl, content: &LabelContent, ap
             label.set_text("Error");
             None
         }
-        LabelContent::Timer { format } => Some(LabelData::Timer {
+        LabelContent::Timer { format, id: None } => Some(LabelData::Timer {
             format: format.clone(),
             start: Instant::now(),
         }),
+        LabelContent::Timer {
+            format,
+            id: Some(id),
+        } => Some(LabelData::NamedTimer {
+            format: format.clone(),
+            id: id.clone(),
+        }),
         LabelContent::Exec { command, interval } => Some(LabelData::Exec {
             last_exec: Instant::now(),
             interval: *interval,
@@ -156,6 +172,7 @@ pub fn modular_label_init(label: &mut ModularControl, content: &LabelContent, ap
         }
         LabelContent::Ipd => Some(LabelData::Ipd { last_ipd: -1. }),
         LabelContent::DragMultiplier => Some(LabelData::DragMultiplier),
+        LabelContent::NextReminder => Some(LabelData::NextReminder),
     };
 
     if let Some(state) = state {
@@ -219,12 +236,14 @@ pub(super) fn label_update(control: &mut ModularControl, _: &mut (), app: &mut A
             }
         }
         LabelData::Timer { format, start } => {
-            let mut format = format.clone().to_lowercase();
-            let duration = start.elapsed().as_secs();
-            format = format.replace("%s", &format!("{:02}", (duration % 60)));
-            format = format.replace("%m", &format!("{:02}", ((duration / 60) % 60)));
-            format = format.replace("%h", &format!("{:02}", ((duration / 60) / 60)));
-            control.set_text(&format);
+            control.set_text(&format_duration(format, start.elapsed()));
+        }
+        LabelData::NamedTimer { format, id } => {
+            let duration = app
+                .timers
+                .get(id)
+                .map_or(Duration::ZERO, crate::timers::Timer::display);
+            control.set_text(&format_duration(format, duration));
         }
         LabelData::Exec {
             last_exec,
@@ -301,5 +320,30 @@ pub(super) fn label_update(control: &mut ModularControl, _: &mut (), app: &mut A
         LabelData::DragMultiplier => {
             control.set_text(&format!("{:.1}", app.session.config.space_drag_multiplier));
         }
+        LabelData::NextReminder => {
+            let text = match app.reminders.next_item() {
+                Some((reminder, true)) => format!("Now: {}", reminder.title),
+                Some((reminder, false)) => {
+                    let due = reminder.due().unwrap_or_else(Local::now); // always set for upcoming
+                    let format = if due.date_naive() == Local::now().date_naive() {
+                        "%H:%M"
+                    } else {
+                        "%a %H:%M"
+                    };
+                    format!("{} {}", due.format(format), reminder.title)
+                }
+                None => String::new(),
+            };
+            control.set_text(&text);
+        }
     }
 }
+
+fn format_duration(format: &str, duration: Duration) -> String {
+    let duration = duration.as_secs();
+    let mut format = format.to_lowercase();
+    format = format.replace("%s", &format!("{:02}", (duration % 60)));
+    format = format.replace("%m", &format!("{:02}", ((duration / 60) % 60)));
+    format = format.replace("%h", &format!("{:02}", ((duration / 60) / 60)));
+    format
+}
@@ -1,5 +1,7 @@
 pub mod button;
 pub mod label;
+pub mod notification_list;
+pub mod text_input;
 
 use std::{fs::File, sync::Arc};
 
@@ -18,11 +20,14 @@ use crate::{
 use self::{
     button::{modular_button_init, ButtonAction, ButtonData, OverlayAction},
     label::{modular_label_init, LabelContent, LabelData},
+    notification_list::{modular_notification_entry_init, NotificationEntryData},
+    text_input::{modular_text_input_init, TextInputConfig, TextInputData},
 };
 
 use super::{
     canvas::{builder::CanvasBuilder, control::Control, Canvas},
-    color_parse, GuiColor, FALLBACK_COLOR,
+    theme::Themed,
+    FALLBACK_COLOR,
 };
 
 type ModularControl = Control<(), ModularData>;
@@ -57,21 +62,21 @@ pub struct OverlayListTemplate {
 pub enum ModularElement {
     Panel {
         rect: [f32; 4],
-        corner_radius: Option<f32>,
+        corner_radius: Option<Themed<f32>>,
         bg_color: Arc<str>,
     },
     Label {
         rect: [f32; 4],
-        corner_radius: Option<f32>,
-        font_size: isize,
+        corner_radius: Option<Themed<f32>>,
+        font_size: Themed<isize>,
         fg_color: Arc<str>,
         #[serde(flatten)]
         data: LabelContent,
     },
     CenteredLabel {
         rect: [f32; 4],
-        corner_radius: Option<f32>,
-        font_size: isize,
+        corner_radius: Option<Themed<f32>>,
+        font_size: Themed<isize>,
         fg_color: Arc<str>,
         #[serde(flatten)]
         data: LabelContent,
@@ -83,19 +88,29 @@ pub enum ModularElement {
     },
     Button {
         rect: [f32; 4],
-        corner_radius: Option<f32>,
-        font_size: isize,
+        corner_radius: Option<Themed<f32>>,
+        font_size: Themed<isize>,
         fg_color: Arc<str>,
         bg_color: Arc<str>,
         text: Arc<str>,
         #[serde(flatten)]
         data: Box<ButtonData>,
     },
+    /// Takes text from the keyboard overlay while focused
+    TextInput {
+        rect: [f32; 4],
+        corner_radius: Option<Themed<f32>>,
+        font_size: Themed<isize>,
+        fg_color: Arc<str>,
+        bg_color: Arc<str>,
+        #[serde(flatten)]
+        config: Box<TextInputConfig>,
+    },
     /// Convenience type to save you from having to create a bunch of labels
     BatteryList {
         rect: [f32; 4],
-        corner_radius: Option<f32>,
-        font_size: isize,
+        corner_radius: Option<Themed<f32>>,
+        font_size: Themed<isize>,
         fg_color: Arc<str>,
         fg_color_low: Arc<str>,
         fg_color_charging: Arc<str>,
@@ -103,10 +118,19 @@ pub enum ModularElement {
         num_devices: usize,
         layout: ListLayout,
     },
+    /// Recent notifications, newest first. Clicking an entry dismisses it.
+    NotificationList {
+        rect: [f32; 4],
+        corner_radius: Option<Themed<f32>>,
+        font_size: Themed<isize>,
+        fg_color: Arc<str>,
+        bg_color: Arc<str>,
+        num_entries: usize,
+    },
     OverlayList {
         rect: [f32; 4],
-        corner_radius: Option<f32>,
-        font_size: isize,
+        corner_radius: Option<Themed<f32>>,
+        font_size: Themed<isize>,
         fg_color: Arc<str>,
         bg_color: Arc<str>,
         layout: ListLayout,
@@ -116,8 +140,8 @@ pub enum ModularElement {
     // Ignored if "wayvr" feature is not enabled
     WayVRLauncher {
         rect: [f32; 4],
-        corner_radius: Option<f32>,
-        font_size: isize,
+        corner_radius: Option<Themed<f32>>,
+        font_size: Themed<isize>,
         fg_color: Arc<str>,
         bg_color: Arc<str>,
         catalog_name: Arc<str>,
@@ -125,8 +149,8 @@ pub enum ModularElement {
     // Ignored if "wayvr" feature is not enabled
     WayVRDisplayList {
         rect: [f32; 4],
-        corner_radius: Option<f32>,
-        font_size: isize,
+        corner_radius: Option<Themed<f32>>,
+        font_size: Themed<isize>,
         fg_color: Arc<str>,
         bg_color: Arc<str>,
     },
@@ -147,6 +171,8 @@ pub enum ListLayout {
 pub enum ModularData {
     Label(Box<LabelData>),
     Button(Box<ButtonData>),
+    TextInput(Box<TextInputData>),
+    NotificationEntry(Box<NotificationEntryData>),
 }
 
 #[allow(clippy::too_many_lines, clippy::many_single_char_names)]
@@ -170,8 +196,14 @@ pub fn modular_canvas(
                 corner_radius,
                 bg_color,
             } => {
-                canvas.bg_color = color_parse(bg_color).unwrap_or(*FALLBACK_COLOR);
-                canvas.panel(*x, *y, *w, *h, corner_radius.unwrap_or_default());
+                canvas.bg_color = state.theme.color(bg_color).unwrap_or(*FALLBACK_COLOR);
+                canvas.panel(
+                    *x,
+                    *y,
+                    *w,
+                    *h,
+                    state.theme.corner_radius(corner_radius.as_ref()),
+                );
             }
             ModularElement::Label {
                 rect: [x, y, w, h],
@@ -180,14 +212,14 @@ pub fn modular_canvas(
                 fg_color,
                 data,
             } => {
-                canvas.font_size = *font_size;
-                canvas.fg_color = color_parse(fg_color).unwrap_or(*FALLBACK_COLOR);
+                canvas.font_size = state.theme.font_size(font_size);
+                canvas.fg_color = state.theme.color(fg_color).unwrap_or(*FALLBACK_COLOR);
                 let label = canvas.label(
                     *x,
                     *y,
                     *w,
                     *h,
-                    corner_radius.unwrap_or_default(),
+                    state.theme.corner_radius(corner_radius.as_ref()),
                     empty_str.clone(),
                 );
                 modular_label_init(label, data, state);
@@ -199,14 +231,14 @@ pub fn modular_canvas(
                 fg_color,
                 data,
             } => {
-                canvas.font_size = *font_size;
-                canvas.fg_color = color_parse(fg_color).unwrap_or(*FALLBACK_COLOR);
+                canvas.font_size = state.theme.font_size(font_size);
+                canvas.fg_color = state.theme.color(fg_color).unwrap_or(*FALLBACK_COLOR);
                 let label = canvas.label_centered(
                     *x,
                     *y,
                     *w,
                     *h,
-                    corner_radius.unwrap_or_default(),
+                    state.theme.corner_radius(corner_radius.as_ref()),
                     empty_str.clone(),
                 );
                 modular_label_init(label, data, state);
@@ -239,19 +271,40 @@ pub fn modular_canvas(
                 text,
                 data,
             } => {
-                canvas.bg_color = color_parse(bg_color).unwrap_or(*FALLBACK_COLOR);
-                canvas.fg_color = color_parse(fg_color).unwrap_or(*FALLBACK_COLOR);
-                canvas.font_size = *font_size;
+                canvas.bg_color = state.theme.color(bg_color).unwrap_or(*FALLBACK_COLOR);
+                canvas.fg_color = state.theme.color(fg_color).unwrap_or(*FALLBACK_COLOR);
+                canvas.font_size = state.theme.font_size(font_size);
                 let button = canvas.button(
                     *x,
                     *y,
                     *w,
                     *h,
-                    corner_radius.unwrap_or_default(),
+                    state.theme.corner_radius(corner_radius.as_ref()),
                     text.clone(),
                 );
                 modular_button_init(button, data);
             }
+            ModularElement::TextInput {
+                rect: [x, y, w, h],
+                corner_radius,
+                font_size,
+                fg_color,
+                bg_color,
+                config,
+            } => {
+                canvas.bg_color = state.theme.color(bg_color).unwrap_or(*FALLBACK_COLOR);
+                canvas.fg_color = state.theme.color(fg_color).unwrap_or(*FALLBACK_COLOR);
+                canvas.font_size = state.theme.font_size(font_size);
+                let text_input = canvas.text_input(
+                    *x,
+                    *y,
+                    *w,
+                    *h,
+                    state.theme.corner_radius(corner_radius.as_ref()),
+                    empty_str.clone(),
+                );
+                modular_text_input_init(text_input, config);
+            }
             ModularElement::BatteryList {
                 rect: [x, y, w, h],
                 corner_radius,
@@ -272,8 +325,8 @@ pub fn modular_canvas(
                     ListLayout::Vertical => (*w, *h / num_buttons),
                 };
 
-                let fg_color = color_parse(fg_color).unwrap_or(*FALLBACK_COLOR);
-                canvas.font_size = *font_size;
+                let fg_color = state.theme.color(fg_color).unwrap_or(*FALLBACK_COLOR);
+                canvas.font_size = state.theme.font_size(font_size);
                 canvas.fg_color = fg_color;
 
                 for i in 0..*num_devices {
@@ -282,7 +335,7 @@ pub fn modular_canvas(
                         button_y + 2.,
                         button_w - 4.,
                         button_h - 4.,
-                        corner_radius.unwrap_or_default(),
+                        state.theme.corner_radius(corner_radius.as_ref()),
                         empty_str.clone(),
                     );
                     modular_label_init(
@@ -306,6 +359,32 @@ pub fn modular_canvas(
                     };
                 }
             }
+            ModularElement::NotificationList {
+                rect: [x, y, w, h],
+                corner_radius,
+                font_size,
+                fg_color,
+                bg_color,
+                num_entries,
+            } => {
+                let entry_h = *h / *num_entries as f32;
+
+                canvas.bg_color = state.theme.color(bg_color).unwrap_or(*FALLBACK_COLOR);
+                canvas.fg_color = state.theme.color(fg_color).unwrap_or(*FALLBACK_COLOR);
+                canvas.font_size = state.theme.font_size(font_size);
+
+                for i in 0..*num_entries {
+                    let entry = canvas.list_item(
+                        x + 2.,
+                        (i as f32).mul_add(entry_h, *y) + 2.,
+                        w - 4.,
+                        entry_h - 4.,
+                        state.theme.corner_radius(corner_radius.as_ref()),
+                        empty_str.clone(),
+                    );
+                    modular_notification_entry_init(entry, i, w - 4.);
+                }
+            }
             ModularElement::OverlayList {
                 rect: [x, y, w, h],
                 corner_radius,
@@ -323,9 +402,9 @@ pub fn modular_canvas(
                     ListLayout::Vertical => (*w, *h / num_buttons),
                 };
 
-                canvas.bg_color = color_parse(bg_color).unwrap_or(*FALLBACK_COLOR);
-                canvas.fg_color = color_parse(fg_color).unwrap_or(*FALLBACK_COLOR);
-                canvas.font_size = *font_size;
+                canvas.bg_color = state.theme.color(bg_color).unwrap_or(*FALLBACK_COLOR);
+                canvas.fg_color = state.theme.color(fg_color).unwrap_or(*FALLBACK_COLOR);
+                canvas.font_size = state.theme.font_size(font_size);
 
                 for screen in &state.screens {
                     let button = canvas.button(
@@ -333,7 +412,7 @@ pub fn modular_canvas(
                         button_y + 2.,
                         button_w - 4.,
                         button_h - 4.,
-                        corner_radius.unwrap_or_default(),
+                        state.theme.corner_radius(corner_radius.as_ref()),
                         screen.name.clone(),
                     );
 
@@ -439,16 +518,18 @@ pub fn modular_canvas(
                             let button_w: f32 = *w / catalog.apps.len() as f32;
                             let button_h: f32 = *h;
 
-                            canvas.bg_color = color_parse(bg_color).unwrap_or(*FALLBACK_COLOR);
-                            canvas.fg_color = color_parse(fg_color).unwrap_or(*FALLBACK_COLOR);
-                            canvas.font_size = *font_size;
+                            canvas.bg_color =
+                                state.theme.color(bg_color).unwrap_or(*FALLBACK_COLOR);
+                            canvas.fg_color =
+                                state.theme.color(fg_color).unwrap_or(*FALLBACK_COLOR);
+                            canvas.font_size = state.theme.font_size(font_size);
 
                             let button = canvas.button(
                                 button_x + 2.,
                                 button_y + 2.,
                                 button_w - 4.,
                                 button_h - 4.,
-                                corner_radius.unwrap_or_default(),
+                                state.theme.corner_radius(corner_radius.as_ref()),
                                 Arc::from(app.name.as_str()),
                             );
 
@@ -491,16 +572,16 @@ pub fn modular_canvas(
                         let button_w: f32 = (*w / displays.len() as f32).min(80.0);
                         let button_h: f32 = *h;
 
-                        canvas.bg_color = color_parse(bg_color).unwrap_or(*FALLBACK_COLOR);
-                        canvas.fg_color = color_parse(fg_color).unwrap_or(*FALLBACK_COLOR);
-                        canvas.font_size = *font_size;
+                        canvas.bg_color = state.theme.color(bg_color).unwrap_or(*FALLBACK_COLOR);
+                        canvas.fg_color = state.theme.color(fg_color).unwrap_or(*FALLBACK_COLOR);
+                        canvas.font_size = state.theme.font_size(font_size);
 
                         let button = canvas.button(
                             button_x + 2.,
                             button_y + 2.,
                             button_w - 4.,
                             button_h - 4.,
-                            corner_radius.unwrap_or_default(),
+                            state.theme.corner_radius(corner_radius.as_ref()),
                             Arc::from(display_name.as_str()),
                         );
 
@@ -534,13 +615,6 @@ pub fn modular_canvas(
     Ok(canvas.build())
 }
 
-pub fn color_parse_or_default(color: &str) -> GuiColor {
-    color_parse(color).unwrap_or_else(|e| {
-        log::error!("Failed to parse color '{color}': {e}");
-        *FALLBACK_COLOR
-    })
-}
-
 fn sprite_from_path(path: Arc<str>, app: &mut AppState) -> anyhow::Result<Arc<ImageView>> {
     if let Some(view) = app.sprites.arc_get(&path) {
         return Ok(view.clone());
@@ -0,0 +1,109 @@
+use chrono::{Local, TimeZone};
+use glam::Vec4;
+
+use crate::{
+    backend::{input::PointerMode, notifications::HistoryEntry},
+    gui::GuiColor,
+    state::AppState,
+};
+
+use super::{ModularControl, ModularData};
+
+/// Approximate glyph width relative to font size, used to cut off long bodies
+const CHAR_WIDTH_RATIO: f32 = 0.55;
+
+pub struct NotificationEntryData {
+    /// Position in the history, newest first
+    index: usize,
+    /// History id of the entry currently shown
+    shown_id: Option<u64>,
+    max_chars: usize,
+    bg_color: GuiColor,
+}
+
+pub fn modular_notification_entry_init(control: &mut ModularControl, index: usize, w: f32) {
+    let data = NotificationEntryData {
+        index,
+        shown_id: None,
+        max_chars: (w / (control.size as f32 * CHAR_WIDTH_RATIO)).max(1.) as usize,
+        bg_color: control.bg_color,
+    };
+    // empty until there is an entry to show
+    control.bg_color = Vec4::ZERO;
+
+    control.state = Some(ModularData::NotificationEntry(Box::new(data)));
+    control.on_update = Some(notification_entry_update);
+    control.on_press = Some(notification_entry_press);
+}
+
+fn format_entry(entry: &HistoryEntry, max_chars: usize) -> String {
+    let time = Local.timestamp_opt(entry.timestamp, 0).single();
+    let time = time.map_or_else(String::new, |time| {
+        if time.date_naive() == Local::now().date_naive() {
+            time.format("%H:%M").to_string()
+        } else {
+            time.format("%b %d %H:%M").to_string()
+        }
+    });
+
+    let header = if entry.title == entry.app_name {
+        format!("{time}  {}", entry.app_name)
+    } else {
+        format!("{time}  {}: {}", entry.app_name, entry.title)
+    };
+    let body = entry.body.lines().next().unwrap_or_default();
+
+    format!(
+        "{}\n{}",
+        truncate(&header, max_chars),
+        truncate(body, max_chars)
+    )
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let mut out: String = text.chars().take(max_chars.saturating_sub(1)).collect();
+    out.push('…');
+    out
+}
+
+fn notification_entry_update(control: &mut ModularControl, _: &mut (), app: &mut AppState) {
+    // want panic
+    let ModularData::NotificationEntry(data) = control.state.as_mut().unwrap() else {
+        panic!("notification_entry_update: control state is not NotificationEntry");
+    };
+
+    let entry = app.notification_history.get(data.index);
+    let id = entry.map(|e| e.id);
+    if id == data.shown_id {
+        return;
+    }
+    data.shown_id = id;
+
+    if let Some(entry) = entry {
+        let text = format_entry(entry, data.max_chars);
+        control.set_bg_color(data.bg_color);
+        control.set_text(&text);
+    } else {
+        control.set_bg_color(Vec4::ZERO);
+        control.set_text("");
+    }
+}
+
+fn notification_entry_press(
+    control: &mut ModularControl,
+    _: &mut (),
+    app: &mut AppState,
+    _mode: PointerMode,
+) {
+    // want panic
+    let ModularData::NotificationEntry(data) = control.state.as_mut().unwrap() else {
+        panic!("notification_entry_press: control state is not NotificationEntry");
+    };
+
+    if let Some(id) = data.shown_id {
+        app.notification_history.dismiss(id);
+    }
+}
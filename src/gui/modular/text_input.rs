@@ -0,0 +1,331 @@
+use std::{
+    rc::{Rc, Weak},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use serde::Deserialize;
+
+use crate::{
+    backend::{common::OverlaySelector, input::PointerMode, task::TaskType},
+    gui::GuiColor,
+    hid::{KeyModifier, VirtualKey, XkbKeymap},
+    overlays::keyboard::KEYBOARD_NAME,
+    state::AppState,
+};
+
+use super::{
+    button::{handle_action, ButtonAction, PressData},
+    ModularControl, ModularData,
+};
+
+static TEXT_INPUT_AUTO_INCREMENT: AtomicUsize = AtomicUsize::new(0);
+
+const CARET: char = '|';
+
+#[derive(Deserialize, Clone)]
+pub struct TextInputConfig {
+    /// Shown in place of the text while the field is empty
+    pub placeholder: Option<Arc<str>>,
+    /// Run on Return. Any `{text}` in the action's strings is replaced by the entered text.
+    pub on_submit: Vec<ButtonAction>,
+    #[serde(default)]
+    pub clear_on_submit: bool,
+}
+
+pub struct TextInputData {
+    id: usize,
+    text: String,
+    fg_color: GuiColor,
+    placeholder: Arc<str>,
+    clear_on_submit: bool,
+    on_submit: Vec<ButtonAction>,
+    press: PressData,
+}
+
+/// The text field that currently receives keystrokes from the keyboard overlay.
+pub struct TextFocus {
+    id: usize,
+    text: String,
+    /// The text of the field when it got the focus, restored on Escape
+    original: String,
+    cursor: usize,
+    modifiers: KeyModifier,
+    submitted: bool,
+    cancelled: bool,
+    /// The canvas holding the text field, set once the press that focused it is handled
+    owner: Option<Weak<()>>,
+}
+
+impl TextFocus {
+    fn new(id: usize, text: String) -> Self {
+        let cursor = text.len();
+        Self {
+            id,
+            original: text.clone(),
+            text,
+            cursor,
+            modifiers: 0,
+            submitted: false,
+            cancelled: false,
+            owner: None,
+        }
+    }
+
+    pub fn claim(&mut self, owner: &Rc<()>) {
+        if self.owner.is_none() {
+            self.owner = Some(Rc::downgrade(owner));
+        }
+    }
+
+    pub fn is_owned_by(&self, owner: &Rc<()>) -> bool {
+        self.owner
+            .as_ref()
+            .is_some_and(|o| std::ptr::eq(o.as_ptr(), Rc::as_ptr(owner)))
+    }
+
+    /// The canvas holding the text field was destroyed
+    fn is_orphaned(&self) -> bool {
+        self.owner.as_ref().is_some_and(|o| o.strong_count() == 0)
+    }
+
+    pub const fn set_modifiers(&mut self, modifiers: KeyModifier) {
+        self.modifiers = modifiers;
+    }
+
+    pub fn insert_str(&mut self, text: &str) {
+        self.text.insert_str(self.cursor, text);
+        self.cursor += text.len();
+    }
+
+    pub fn send_key(&mut self, keymap: Option<&XkbKeymap>, key: VirtualKey) {
+        // the field has yet to pick up the result
+        if self.submitted || self.cancelled {
+            return;
+        }
+        match key {
+            VirtualKey::Return | VirtualKey::KP_Enter => self.submitted = true,
+            VirtualKey::Escape => {
+                self.text.clone_from(&self.original);
+                self.cursor = self.text.len();
+                self.cancelled = true;
+            }
+            VirtualKey::BackSpace => {
+                if let Some(c) = self.text[..self.cursor].chars().next_back() {
+                    self.cursor -= c.len_utf8();
+                    self.text.remove(self.cursor);
+                }
+            }
+            VirtualKey::Delete => {
+                if self.cursor < self.text.len() {
+                    self.text.remove(self.cursor);
+                }
+            }
+            VirtualKey::Left => {
+                if let Some(c) = self.text[..self.cursor].chars().next_back() {
+                    self.cursor -= c.len_utf8();
+                }
+            }
+            VirtualKey::Right => {
+                if let Some(c) = self.text[self.cursor..].chars().next() {
+                    self.cursor += c.len_utf8();
+                }
+            }
+            VirtualKey::Home => self.cursor = 0,
+            VirtualKey::End => self.cursor = self.text.len(),
+            // single line, nothing to move to
+            VirtualKey::Up | VirtualKey::Down => {}
+            _ => {
+                let Some(keymap) = keymap else {
+                    return;
+                };
+                let text = keymap.text_for_key(key, self.modifiers);
+                if !text.chars().any(char::is_control) {
+                    self.insert_str(&text);
+                }
+            }
+        }
+    }
+
+    fn display_text(&self) -> String {
+        let mut display = self.text.clone();
+        display.insert(self.cursor, CARET);
+        display
+    }
+}
+
+/// Drops the text focus if its text field no longer exists
+pub fn clear_orphaned_focus(app: &mut AppState) {
+    if app.text_focus.as_ref().is_some_and(TextFocus::is_orphaned) {
+        app.text_focus = None;
+    }
+}
+
+pub fn modular_text_input_init(control: &mut ModularControl, config: &TextInputConfig) {
+    let data = TextInputData {
+        id: TEXT_INPUT_AUTO_INCREMENT.fetch_add(1, Ordering::Relaxed),
+        text: String::new(),
+        fg_color: control.fg_color,
+        placeholder: config.placeholder.clone().unwrap_or_else(|| "".into()),
+        clear_on_submit: config.clear_on_submit,
+        on_submit: config.on_submit.clone(),
+        press: PressData::default(),
+    };
+
+    control.set_text(&data.placeholder);
+    control.set_fg_color(data.fg_color * 0.6);
+    control.state = Some(ModularData::TextInput(Box::new(data)));
+    control.on_press = Some(text_input_press);
+    control.on_update = Some(text_input_update);
+}
+
+fn text_input_press(
+    control: &mut ModularControl,
+    _: &mut (),
+    app: &mut AppState,
+    _mode: PointerMode,
+) {
+    // want panic
+    let ModularData::TextInput(data) = control.state.as_mut().unwrap() else {
+        panic!("text_input_press: control state is not TextInput");
+    };
+
+    if app.text_focus.as_ref().is_some_and(|f| f.id == data.id) {
+        return;
+    }
+
+    app.text_focus = Some(TextFocus::new(data.id, data.text.clone()));
+
+    app.tasks.enqueue(TaskType::Overlay(
+        OverlaySelector::Name(KEYBOARD_NAME.into()),
+        Box::new(|app, o| {
+            if !o.want_visible {
+                o.want_visible = true;
+                if o.recenter {
+                    o.reset(app, false);
+                }
+            }
+        }),
+    ));
+}
+
+fn text_input_update(control: &mut ModularControl, _: &mut (), app: &mut AppState) {
+    // want panic
+    let ModularData::TextInput(data) = control.state.as_mut().unwrap() else {
+        panic!("text_input_update: control state is not TextInput");
+    };
+
+    let focus = app.text_focus.as_ref().filter(|f| f.id == data.id);
+
+    if let Some(focus) = focus {
+        if !focus.submitted && !focus.cancelled {
+            control.set_fg_color(data.fg_color);
+            control.set_text(&focus.display_text());
+            return;
+        }
+
+        // safe because we just checked
+        let focus = app.text_focus.take().unwrap();
+        // on Escape, the focus already went back to the text from before editing
+        data.text = focus.text;
+
+        if focus.submitted {
+            for action in &data.on_submit {
+                handle_action(&action.with_text(&data.text), &mut data.press, app);
+            }
+
+            if data.clear_on_submit {
+                data.text.clear();
+            }
+        }
+    }
+
+    if data.text.is_empty() {
+        control.set_fg_color(data.fg_color * 0.6);
+        control.set_text(&data.placeholder);
+    } else {
+        control.set_fg_color(data.fg_color);
+        control.set_text(&data.text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(focus: &mut TextFocus, keys: &[VirtualKey]) {
+        for key in keys {
+            focus.send_key(None, *key);
+        }
+    }
+
+    #[test]
+    fn backspace_removes_whole_utf8_char() {
+        let mut focus = TextFocus::new(0, "aé€".into());
+        press(&mut focus, &[VirtualKey::BackSpace]);
+        assert_eq!(focus.text, "aé");
+        assert_eq!(focus.cursor, focus.text.len());
+        press(&mut focus, &[VirtualKey::BackSpace, VirtualKey::BackSpace]);
+        assert_eq!(focus.text, "");
+        press(&mut focus, &[VirtualKey::BackSpace]);
+        assert_eq!((focus.text.as_str(), focus.cursor), ("", 0));
+    }
+
+    #[test]
+    fn delete_removes_whole_utf8_char() {
+        let mut focus = TextFocus::new(0, "aéb".into());
+        press(&mut focus, &[VirtualKey::Home, VirtualKey::Right]);
+        assert_eq!(focus.cursor, 1);
+        press(&mut focus, &[VirtualKey::Delete]);
+        assert_eq!(focus.text, "ab");
+        assert_eq!(focus.cursor, 1);
+        press(&mut focus, &[VirtualKey::End, VirtualKey::Delete]);
+        assert_eq!(focus.text, "ab");
+    }
+
+    #[test]
+    fn cursor_steps_over_utf8_chars() {
+        let mut focus = TextFocus::new(0, "€€".into());
+        press(&mut focus, &[VirtualKey::Left]);
+        assert_eq!(focus.cursor, 3);
+        press(&mut focus, &[VirtualKey::Left, VirtualKey::Left]);
+        assert_eq!(focus.cursor, 0);
+        press(&mut focus, &[VirtualKey::Right]);
+        assert_eq!(focus.cursor, 3);
+    }
+
+    #[test]
+    fn inserts_in_the_middle() {
+        let mut focus = TextFocus::new(0, "ac".into());
+        press(&mut focus, &[VirtualKey::Left]);
+        focus.insert_str("bb");
+        assert_eq!(focus.text, "abbc");
+        assert_eq!(focus.cursor, 3);
+        assert_eq!(focus.display_text(), "abb|c");
+    }
+
+    #[test]
+    fn up_and_down_are_ignored() {
+        let mut focus = TextFocus::new(0, "abc".into());
+        press(
+            &mut focus,
+            &[VirtualKey::Left, VirtualKey::Up, VirtualKey::Down],
+        );
+        assert_eq!(focus.cursor, 2);
+    }
+
+    #[test]
+    fn escape_reverts_to_the_original_text() {
+        let mut focus = TextFocus::new(0, "old".into());
+        press(&mut focus, &[VirtualKey::BackSpace]);
+        focus.insert_str("new");
+        press(&mut focus, &[VirtualKey::Escape]);
+        assert!(focus.cancelled);
+        assert_eq!(focus.text, "old");
+        // nothing is typed until the field picks up the cancellation
+        focus.send_key(None, VirtualKey::BackSpace);
+        assert_eq!(focus.text, "old");
+    }
+}
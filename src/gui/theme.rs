@@ -0,0 +1,93 @@
+use std::{collections::HashMap, sync::Arc};
+
+use serde::Deserialize;
+
+use crate::config::{load_known_yaml, load_theme, ConfigType};
+
+use super::{color_parse, GuiColor, FALLBACK_COLOR};
+
+const DEFAULT_THEME: &str = "default";
+const FALLBACK_FONT_SIZE: isize = 16;
+
+/// A literal value, or a `$name` reference into the active theme
+#[derive(Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum Themed<T> {
+    Value(T),
+    Var(Arc<str>),
+}
+
+#[derive(Deserialize, Default)]
+pub struct Theme {
+    #[serde(default)]
+    colors: HashMap<Arc<str>, Arc<str>>,
+    #[serde(default)]
+    font_sizes: HashMap<Arc<str>, isize>,
+    #[serde(default)]
+    corner_radii: HashMap<Arc<str>, f32>,
+}
+
+impl Theme {
+    /// Loads the default theme, with the named theme applied on top.
+    pub fn load(name: &str) -> Self {
+        let mut theme = load_known_yaml::<Self>(ConfigType::Theme);
+        if name.is_empty() || name == DEFAULT_THEME {
+            return theme;
+        }
+
+        match load_theme(name) {
+            Ok(overrides) => {
+                theme.colors.extend(overrides.colors);
+                theme.font_sizes.extend(overrides.font_sizes);
+                theme.corner_radii.extend(overrides.corner_radii);
+            }
+            Err(e) => {
+                log::error!("Failed to load theme '{name}', using default: {e:?}");
+            }
+        }
+        theme
+    }
+
+    // Parses a color from a HTML hex string or a $name reference
+    pub fn color(&self, color: &str) -> anyhow::Result<GuiColor> {
+        let Some(var) = color.strip_prefix('$') else {
+            return color_parse(color);
+        };
+        let Some(value) = self.colors.get(var) else {
+            anyhow::bail!("Unknown theme color: '{color}'");
+        };
+        color_parse(value)
+    }
+
+    pub fn color_or_default(&self, color: &str) -> GuiColor {
+        self.color(color).unwrap_or_else(|e| {
+            log::error!("Failed to parse color '{color}': {e}");
+            *FALLBACK_COLOR
+        })
+    }
+
+    pub fn font_size(&self, size: &Themed<isize>) -> isize {
+        match size {
+            Themed::Value(size) => *size,
+            Themed::Var(var) => Self::lookup(&self.font_sizes, var).unwrap_or_else(|| {
+                log::error!("Unknown theme font size: '{var}'");
+                FALLBACK_FONT_SIZE
+            }),
+        }
+    }
+
+    pub fn corner_radius(&self, radius: Option<&Themed<f32>>) -> f32 {
+        match radius {
+            None => 0.,
+            Some(Themed::Value(radius)) => *radius,
+            Some(Themed::Var(var)) => Self::lookup(&self.corner_radii, var).unwrap_or_else(|| {
+                log::error!("Unknown theme corner radius: '{var}'");
+                0.
+            }),
+        }
+    }
+
+    fn lookup<T: Copy>(map: &HashMap<Arc<str>, T>, var: &str) -> Option<T> {
+        map.get(var.strip_prefix('$').unwrap_or(var)).copied()
+    }
+}
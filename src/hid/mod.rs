@@ -2,11 +2,12 @@ use glam::{IVec2, Vec2};
 use idmap::{idmap, IdMap};
 use idmap_derive::IntegerId;
 use input_linux::{
-    AbsoluteAxis, AbsoluteInfo, AbsoluteInfoSetup, EventKind, InputId, Key, RelativeAxis,
-    UInputHandle,
+    AbsoluteAxis, AbsoluteInfo, AbsoluteInfoSetup, EventKind, InputId, InputProperty, Key,
+    RelativeAxis, UInputHandle,
 };
 use libc::{input_event, timeval};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::mem::transmute;
 use std::sync::LazyLock;
 use std::{fs::File, sync::atomic::AtomicBool};
@@ -21,25 +22,106 @@ mod x11;
 
 pub static USE_UINPUT: AtomicBool = AtomicBool::new(true);
 
-pub fn initialize() -> Box<dyn HidProvider> {
+/// How keyboard and mouse input is sent to the desktop
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum HidBackend {
+    /// uinput, then the first of the others that works
+    #[default]
+    Auto,
+    Uinput,
+    /// Virtual keyboard and pointer protocols of wlroots-based compositors
+    Wayland,
+    /// XTest extension of the X server
+    XTest,
+}
+
+/// With `touch`, uinput also gets a multi-touch device
+pub fn initialize(backend: HidBackend, touch: bool) -> Box<dyn HidProvider> {
     if !USE_UINPUT.load(std::sync::atomic::Ordering::Relaxed) {
         log::info!("Uinput disabled by user.");
         return Box::new(DummyProvider {});
     }
 
-    if let Some(uinput) = UInputProvider::try_new() {
-        log::info!("Initialized uinput.");
-        return Box::new(uinput);
+    let provider = match backend {
+        HidBackend::Auto => try_uinput(touch).or_else(|| {
+            // XTest only reaches X11 apps when running under a Wayland session
+            if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+                try_wayland()
+            } else {
+                try_xtest()
+            }
+        }),
+        HidBackend::Uinput => try_uinput(touch),
+        HidBackend::Wayland => try_wayland(),
+        HidBackend::XTest => try_xtest(),
+    };
+    if let Some(provider) = provider {
+        return provider;
     }
-    log::error!("Could not create uinput provider. Keyboard/Mouse input will not work!");
-    log::error!("To check if you're in input group, run: id -nG");
-    if let Ok(user) = std::env::var("USER") {
-        log::error!("To add yourself to the input group, run: sudo usermod -aG input {user}");
-        log::error!("After adding yourself to the input group, you will need to reboot.");
+
+    log::error!("Could not create {backend:?} HID provider. Keyboard/Mouse input will not work!");
+    if matches!(backend, HidBackend::Auto | HidBackend::Uinput) {
+        log::error!("To check if you're in input group, run: id -nG");
+        if let Ok(user) = std::env::var("USER") {
+            log::error!("To add yourself to the input group, run: sudo usermod -aG input {user}");
+            log::error!("After adding yourself to the input group, you will need to reboot.");
+        }
     }
     Box::new(DummyProvider {})
 }
 
+fn try_uinput(touch: bool) -> Option<Box<dyn HidProvider>> {
+    let mut uinput = UInputProvider::try_new()?;
+    log::info!("Initialized uinput.");
+    if touch {
+        uinput.touch = TouchDevice::try_new();
+        if uinput.touch.is_none() {
+            log::error!("Could not create uinput touch device.");
+        }
+    }
+    Some(Box::new(uinput))
+}
+
+#[cfg(feature = "wayland")]
+fn try_wayland() -> Option<Box<dyn HidProvider>> {
+    match wayland::WlVirtualProvider::try_new() {
+        Ok(provider) => {
+            log::info!("Initialized Wayland virtual keyboard/pointer.");
+            Some(Box::new(provider))
+        }
+        Err(e) => {
+            log::warn!("Wayland virtual keyboard/pointer not available: {e:?}");
+            None
+        }
+    }
+}
+
+#[cfg(not(feature = "wayland"))]
+fn try_wayland() -> Option<Box<dyn HidProvider>> {
+    log::warn!("Wayland support not enabled.");
+    None
+}
+
+#[cfg(feature = "x11")]
+fn try_xtest() -> Option<Box<dyn HidProvider>> {
+    match x11::XTestProvider::try_new() {
+        Ok(provider) => {
+            log::info!("Initialized XTest.");
+            Some(Box::new(provider))
+        }
+        Err(e) => {
+            log::warn!("XTest not available: {e:?}");
+            None
+        }
+    }
+}
+
+#[cfg(not(feature = "x11"))]
+fn try_xtest() -> Option<Box<dyn HidProvider>> {
+    log::warn!("X11 support not enabled.");
+    None
+}
+
 pub trait HidProvider {
     fn mouse_move(&mut self, pos: Vec2);
     fn send_button(&mut self, button: u16, down: bool);
@@ -49,6 +131,13 @@ pub trait HidProvider {
     fn set_desktop_extent(&mut self, extent: Vec2);
     fn set_desktop_origin(&mut self, origin: Vec2);
     fn commit(&mut self);
+
+    /// Whether `touch` does anything
+    fn has_touch(&self) -> bool {
+        false
+    }
+    /// Puts down or moves the given contact, or lifts it with `None`
+    fn touch(&mut self, _contact: usize, _pos: Option<Vec2>) {}
 }
 
 struct MouseButtonAction {
@@ -71,6 +160,23 @@ pub struct UInputProvider {
     desktop_origin: Vec2,
     cur_modifiers: u8,
     current_action: MouseAction,
+    touch: Option<TouchDevice>,
+}
+
+/// Multi-touch screen with a contact for each VR pointer
+struct TouchDevice {
+    handle: UInputHandle<File>,
+    contacts: [TouchContact; MAX_TOUCH_CONTACTS],
+    next_tracking_id: i32,
+}
+
+#[derive(Default, Clone, Copy)]
+struct TouchContact {
+    /// Requested position, `None` if lifted
+    pos: Option<Vec2>,
+    /// Tracking ID sent to the device, -1 if lifted
+    tracking_id: i32,
+    dirty: bool,
 }
 
 pub struct DummyProvider;
@@ -81,6 +187,9 @@ pub const MOUSE_MIDDLE: u16 = 0x112;
 
 const MOUSE_EXTENT: f32 = 32768.;
 
+const MAX_TOUCH_CONTACTS: usize = 4;
+const BTN_TOUCH: u16 = 0x14a;
+
 const EV_SYN: u16 = 0x0;
 const EV_KEY: u16 = 0x1;
 const EV_REL: u16 = 0x2;
@@ -167,6 +276,7 @@ impl UInputProvider {
             desktop_origin: Vec2::ZERO,
             current_action: MouseAction::default(),
             cur_modifiers: 0,
+            touch: None,
         })
     }
     fn send_button_internal(&self, button: u16, down: bool) {
@@ -213,6 +323,140 @@ impl UInputProvider {
     }
 }
 
+impl TouchDevice {
+    fn try_new() -> Option<Self> {
+        let handle = UInputHandle::new(File::create("/dev/uinput").ok()?);
+
+        let id = InputId {
+            bustype: 0x03,
+            vendor: 0x4711,
+            product: 0x0831,
+            version: 5,
+        };
+        let name = b"WlxOverlay-S Touch\0";
+
+        let abs = |axis, maximum| AbsoluteInfoSetup {
+            axis,
+            info: AbsoluteInfo {
+                value: 0,
+                minimum: 0,
+                maximum,
+                fuzz: 0,
+                flat: 0,
+                resolution: 10,
+            },
+        };
+        let extent = MOUSE_EXTENT as i32;
+        let abs_info = vec![
+            abs(AbsoluteAxis::X, extent),
+            abs(AbsoluteAxis::Y, extent),
+            abs(AbsoluteAxis::MultitouchSlot, MAX_TOUCH_CONTACTS as i32 - 1),
+            abs(AbsoluteAxis::MultitouchTrackingId, i32::from(u16::MAX)),
+            abs(AbsoluteAxis::MultitouchPositionX, extent),
+            abs(AbsoluteAxis::MultitouchPositionY, extent),
+        ];
+
+        // a direct device is a touchscreen rather than a touchpad
+        handle.set_propbit(InputProperty::Direct).ok()?;
+        handle.set_evbit(EventKind::Key).ok()?;
+        handle
+            .set_keybit(unsafe { transmute::<u16, Key>(BTN_TOUCH) })
+            .ok()?;
+        handle.set_evbit(EventKind::Absolute).ok()?;
+        for info in &abs_info {
+            handle.set_absbit(info.axis).ok()?;
+        }
+        handle.create(&id, name, 0, &abs_info).ok()?;
+
+        Some(Self {
+            handle,
+            contacts: [TouchContact {
+                tracking_id: -1,
+                ..Default::default()
+            }; MAX_TOUCH_CONTACTS],
+            next_tracking_id: 0,
+        })
+    }
+
+    fn set(&mut self, contact: usize, pos: Option<Vec2>) {
+        if let Some(c) = self.contacts.get_mut(contact) {
+            c.pos = pos;
+            c.dirty = true;
+        }
+    }
+
+    fn commit(&mut self, origin: Vec2, extent: Vec2) {
+        if !self.contacts.iter().any(|c| c.dirty) {
+            return;
+        }
+
+        let was_touching = self.contacts.iter().any(|c| c.tracking_id >= 0);
+        let time = get_time();
+        let mut events = Vec::new();
+        for (slot, c) in self.contacts.iter_mut().enumerate() {
+            if !c.dirty {
+                continue;
+            }
+            c.dirty = false;
+            events.push(new_event(
+                time,
+                EV_ABS,
+                AbsoluteAxis::MultitouchSlot as _,
+                slot as i32,
+            ));
+            let Some(pos) = c.pos else {
+                c.tracking_id = -1;
+                events.push(new_event(
+                    time,
+                    EV_ABS,
+                    AbsoluteAxis::MultitouchTrackingId as _,
+                    -1,
+                ));
+                continue;
+            };
+            if c.tracking_id < 0 {
+                c.tracking_id = self.next_tracking_id;
+                self.next_tracking_id = (self.next_tracking_id + 1) % i32::from(u16::MAX);
+                events.push(new_event(
+                    time,
+                    EV_ABS,
+                    AbsoluteAxis::MultitouchTrackingId as _,
+                    c.tracking_id,
+                ));
+            }
+            let pos = (pos - origin) * (MOUSE_EXTENT / extent);
+            events.push(new_event(
+                time,
+                EV_ABS,
+                AbsoluteAxis::MultitouchPositionX as _,
+                pos.x as i32,
+            ));
+            events.push(new_event(
+                time,
+                EV_ABS,
+                AbsoluteAxis::MultitouchPositionY as _,
+                pos.y as i32,
+            ));
+        }
+
+        // single-touch emulation follows the first contact
+        let touching = self.contacts.iter().any(|c| c.tracking_id >= 0);
+        if touching != was_touching {
+            events.push(new_event(time, EV_KEY, BTN_TOUCH, touching.into()));
+        }
+        if let Some(pos) = self.contacts.iter().find_map(|c| c.pos) {
+            let pos = (pos - origin) * (MOUSE_EXTENT / extent);
+            events.push(new_event(time, EV_ABS, AbsoluteAxis::X as _, pos.x as i32));
+            events.push(new_event(time, EV_ABS, AbsoluteAxis::Y as _, pos.y as i32));
+        }
+        events.push(new_event(time, EV_SYN, 0, 0));
+
+        if let Err(res) = self.handle.write(&events) {
+            log::error!("touch: {res}");
+        }
+    }
+}
+
 impl HidProvider for UInputProvider {
     fn set_modifiers(&mut self, modifiers: u8) {
         let changed = self.cur_modifiers ^ modifiers;
@@ -275,6 +519,17 @@ impl HidProvider for UInputProvider {
         if let Some(scroll) = self.current_action.scroll.take() {
             self.wheel_internal(scroll.y, scroll.x);
         }
+        if let Some(touch) = self.touch.as_mut() {
+            touch.commit(self.desktop_origin, self.desktop_extent);
+        }
+    }
+    fn has_touch(&self) -> bool {
+        self.touch.is_some()
+    }
+    fn touch(&mut self, contact: usize, pos: Option<Vec2>) {
+        if let Some(touch) = self.touch.as_mut() {
+            touch.set(contact, pos);
+        }
     }
 }
 
@@ -539,11 +794,101 @@ pub const fn get_key_type(key: VirtualKey) -> KeyType {
     }
 }
 
+/// Spacing forms of the accents of common dead keys
+const DEAD_KEY_ACCENTS: [(&str, &str); 14] = [
+    ("grave", "`"),
+    ("acute", "´"),
+    ("circumflex", "^"),
+    ("tilde", "~"),
+    ("macron", "¯"),
+    ("breve", "˘"),
+    ("abovedot", "˙"),
+    ("diaeresis", "¨"),
+    ("abovering", "˚"),
+    ("doubleacute", "˝"),
+    ("caron", "ˇ"),
+    ("cedilla", "¸"),
+    ("ogonek", "˛"),
+    ("iota", "ͺ"),
+];
+
+#[derive(Clone)]
 pub struct XkbKeymap {
     pub keymap: xkb::Keymap,
 }
 
 impl XkbKeymap {
+    /// The text produced by the key while the given modifiers are held.
+    /// Unlike `label_for_key`, this accepts any combination of modifiers.
+    pub fn text_for_key(&self, key: VirtualKey, modifiers: KeyModifier) -> String {
+        self.state_with(modifiers)
+            .key_get_utf8(xkb::Keycode::from(key as u32))
+    }
+
+    fn state_with(&self, modifiers: KeyModifier) -> xkb::State {
+        let mut state = xkb::State::new(&self.keymap);
+        for i in 0..8 {
+            let m = 1 << i;
+            if modifiers & m == 0 {
+                continue;
+            }
+            if let Some(mod_key) = MODS_TO_KEYS.get(m) {
+                state.update_key(
+                    xkb::Keycode::from(mod_key[0] as u32),
+                    xkb::KeyDirection::Down,
+                );
+            }
+        }
+        state
+    }
+
+    /// Legends of the key for the base, Shift, AltGr and AltGr+Shift levels.
+    /// Dead keys show their accent.
+    pub fn levels_for_key(&self, key: VirtualKey) -> [String; 4] {
+        [0, SHIFT, META, META | SHIFT].map(|modifiers| {
+            let text = self.text_for_key(key, modifiers);
+            if text.is_empty() {
+                self.dead_key_legend(key, modifiers).unwrap_or_default()
+            } else {
+                text
+            }
+        })
+    }
+
+    /// Which key and modifiers type each character, preferring fewer modifiers.
+    pub fn char_map(&self) -> HashMap<char, (VirtualKey, KeyModifier)> {
+        let mut map = HashMap::new();
+        for modifiers in [0, SHIFT, META, META | SHIFT] {
+            let state = self.state_with(modifiers);
+            for key in VirtualKey::iter() {
+                let text = state.key_get_utf8(xkb::Keycode::from(key as u32));
+                let mut chars = text.chars();
+                if let (Some(c), None) = (chars.next(), chars.next()) {
+                    map.entry(c).or_insert((key, modifiers));
+                }
+            }
+        }
+        map
+    }
+
+    pub fn is_dead_key(&self, key: VirtualKey, modifiers: KeyModifier) -> bool {
+        self.dead_key_legend(key, modifiers).is_some()
+    }
+
+    fn dead_key_legend(&self, key: VirtualKey, modifiers: KeyModifier) -> Option<String> {
+        let sym = self
+            .state_with(modifiers)
+            .key_get_one_sym(xkb::Keycode::from(key as u32));
+        let name = xkb::keysym_get_name(sym);
+        let accent = name.strip_prefix("dead_")?;
+        Some(
+            DEAD_KEY_ACCENTS
+                .iter()
+                .find(|(n, _)| *n == accent)
+                .map_or_else(|| accent.to_string(), |(_, a)| (*a).to_string()),
+        )
+    }
+
     pub fn label_for_key(&self, key: VirtualKey, modifier: KeyModifier) -> String {
         let mut state = xkb::State::new(&self.keymap);
         if modifier > 0 {
@@ -557,6 +902,12 @@ impl XkbKeymap {
         state.key_get_utf8(xkb::Keycode::from(key as u32))
     }
 
+    /// Name of the first layout in the keymap, such as "English (US)"
+    pub fn layout_name(&self) -> Option<&str> {
+        let name = self.keymap.layout_get_name(0);
+        (!name.is_empty()).then_some(name)
+    }
+
     pub fn has_altgr(&self) -> bool {
         let state0 = xkb::State::new(&self.keymap);
         let mut state1 = xkb::State::new(&self.keymap);
@@ -587,6 +938,22 @@ impl XkbKeymap {
     }
 }
 
+/// en-US keymap compiled from the system XKB data, for when the desktop layout is unknown.
+pub fn get_keymap_fallback() -> anyhow::Result<XkbKeymap> {
+    let context = xkb::Context::new(xkb::CONTEXT_NO_FLAGS);
+    xkb::Keymap::new_from_names(
+        &context,
+        "",
+        "",
+        "us",
+        "",
+        None,
+        xkb::KEYMAP_COMPILE_NO_FLAGS,
+    )
+    .map(|keymap| XkbKeymap { keymap })
+    .ok_or_else(|| anyhow::anyhow!("Could not compile fallback keymap"))
+}
+
 #[cfg(feature = "wayland")]
 pub use wayland::get_keymap_wl;
 
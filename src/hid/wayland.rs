@@ -1,15 +1,42 @@
+use std::{
+    cell::RefCell,
+    fs::File,
+    io::Write,
+    os::fd::{AsFd, FromRawFd},
+    time::Instant,
+};
+
+use glam::{IVec2, Vec2, Vec2Swizzles};
+use wayland_protocols::xdg::xdg_output::zv1::client::{
+    zxdg_output_manager_v1::ZxdgOutputManagerV1,
+    zxdg_output_v1::{self, ZxdgOutputV1},
+};
+use wayland_protocols_misc::zwp_virtual_keyboard_v1::client::{
+    zwp_virtual_keyboard_manager_v1::ZwpVirtualKeyboardManagerV1,
+    zwp_virtual_keyboard_v1::ZwpVirtualKeyboardV1,
+};
+use wayland_protocols_wlr::virtual_pointer::v1::client::{
+    zwlr_virtual_pointer_manager_v1::ZwlrVirtualPointerManagerV1,
+    zwlr_virtual_pointer_v1::ZwlrVirtualPointerV1,
+};
 use wlx_capture::wayland::wayland_client::{
+    delegate_noop,
     globals::{registry_queue_init, GlobalListContents},
     protocol::{
         wl_keyboard::{self, WlKeyboard},
-        wl_registry::WlRegistry,
+        wl_output::{self, Transform, WlOutput},
+        wl_pointer::{Axis, AxisSource, ButtonState},
+        wl_registry::{self, WlRegistry},
         wl_seat::{self, Capability, WlSeat},
     },
-    Connection, Dispatch, Proxy, QueueHandle,
+    Connection, Dispatch, EventQueue, Proxy, QueueHandle, WEnum,
 };
 use xkbcommon::xkb;
 
-use super::XkbKeymap;
+use super::{
+    get_keymap_fallback, HidProvider, KeyModifier, MouseAction, MouseButtonAction, VirtualKey,
+    XkbKeymap, MODS_TO_KEYS,
+};
 
 struct WlKeymapHandler {
     seat: WlSeat,
@@ -138,3 +165,767 @@ impl Dispatch<WlKeyboard, ()> for WlKeymapHandler {
         }
     }
 }
+
+/// Emulates input through the `zwp_virtual_keyboard_v1` and `zwlr_virtual_pointer_v1`
+/// protocols, for wlroots-based compositors where uinput is not available.
+pub struct WlVirtualProvider {
+    connection: Connection,
+    queue: EventQueue<WlVirtualState>,
+    state: WlVirtualState,
+    keyboard: ZwpVirtualKeyboardV1,
+    xkb_state: RefCell<xkb::State>,
+    /// Spans the whole desktop, used when no output contains the position
+    pointer: ZwlrVirtualPointerV1,
+    desktop_extent: Vec2,
+    desktop_origin: Vec2,
+    cur_modifiers: u8,
+    current_action: MouseAction,
+    start: Instant,
+}
+
+struct WlVirtualState {
+    seat: WlSeat,
+    pointer_manager: ZwlrVirtualPointerManagerV1,
+    /// Provides the logical geometry of outputs, if the compositor supports it
+    xdg_output_manager: Option<ZxdgOutputManagerV1>,
+    outputs: Vec<WlVirtualOutput>,
+}
+
+struct WlVirtualOutput {
+    /// Name of the `wl_output` global
+    name: u32,
+    output: WlOutput,
+    xdg_output: Option<ZxdgOutputV1>,
+    position: IVec2,
+    mode: IVec2,
+    scale: i32,
+    transform: Transform,
+    /// From `zxdg_output_v1`, which also covers fractional scaling
+    logical_position: Option<IVec2>,
+    logical_size: Option<IVec2>,
+    /// Maps absolute motion to this output only, created on first use
+    pointer: Option<ZwlrVirtualPointerV1>,
+}
+
+impl WlVirtualOutput {
+    /// Position and size in the compositor's logical coordinates
+    fn logical_rect(&self) -> (Vec2, Vec2) {
+        if let (Some(position), Some(size)) = (self.logical_position, self.logical_size) {
+            return (position.as_vec2(), size.as_vec2());
+        }
+
+        // without xdg-output, assume an integer scale
+        let mut size = self.mode.as_vec2() / self.scale.max(1) as f32;
+        if matches!(
+            self.transform,
+            Transform::_90 | Transform::_270 | Transform::Flipped90 | Transform::Flipped270
+        ) {
+            size = size.yx();
+        }
+        (self.position.as_vec2(), size)
+    }
+
+    fn destroy(&self) {
+        if let Some(pointer) = &self.pointer {
+            pointer.destroy();
+        }
+        if let Some(xdg_output) = &self.xdg_output {
+            xdg_output.destroy();
+        }
+        if self.output.version() >= 3 {
+            self.output.release();
+        }
+    }
+}
+
+impl WlVirtualProvider {
+    pub fn try_new() -> anyhow::Result<Self> {
+        let connection = Connection::connect_to_env()?;
+        let keymap = get_keymap_wl().or_else(|_| get_keymap_fallback())?;
+        Self::with_connection(connection, &keymap)
+    }
+
+    fn with_connection(connection: Connection, keymap: &XkbKeymap) -> anyhow::Result<Self> {
+        let (globals, mut queue) = registry_queue_init::<WlVirtualState>(&connection)?;
+        let qh = queue.handle();
+
+        let seat: WlSeat = globals.bind(&qh, 1..=9, ())?;
+        let keyboard_manager: ZwpVirtualKeyboardManagerV1 = globals.bind(&qh, 1..=1, ())?;
+        let pointer_manager: ZwlrVirtualPointerManagerV1 = globals.bind(&qh, 1..=2, ())?;
+        let xdg_output_manager: Option<ZxdgOutputManagerV1> = globals.bind(&qh, 1..=3, ()).ok();
+
+        let keyboard = keyboard_manager.create_virtual_keyboard(&seat, &qh, ());
+        let keymap_file = keymap_to_file(keymap)?;
+        let keymap_size = keymap_file.metadata()?.len();
+        keyboard.keymap(
+            wl_keyboard::KeymapFormat::XkbV1 as u32,
+            keymap_file.as_fd(),
+            keymap_size as u32,
+        );
+        // the keyboard manager has no destructor request, the proxy is simply dropped
+
+        let pointer = pointer_manager.create_virtual_pointer(Some(&seat), &qh, ());
+
+        let mut state = WlVirtualState {
+            seat,
+            pointer_manager,
+            xdg_output_manager,
+            outputs: Vec::new(),
+        };
+
+        globals.contents().with_list(|list| {
+            for global in list {
+                if global.interface == WlOutput::interface().name {
+                    state.bind_output(globals.registry(), global.name, global.version, &qh);
+                }
+            }
+        });
+
+        // receive the output geometry
+        queue.roundtrip(&mut state)?;
+
+        Ok(Self {
+            connection,
+            queue,
+            state,
+            keyboard,
+            xkb_state: RefCell::new(xkb::State::new(&keymap.keymap)),
+            pointer,
+            desktop_extent: Vec2::ZERO,
+            desktop_origin: Vec2::ZERO,
+            cur_modifiers: 0,
+            current_action: MouseAction::default(),
+            start: Instant::now(),
+        })
+    }
+
+    fn time(&self) -> u32 {
+        self.start.elapsed().as_millis() as u32
+    }
+
+    /// Handles output changes without blocking
+    fn dispatch_events(&mut self) {
+        if let Some(guard) = self.queue.prepare_read() {
+            let _ = guard.read();
+        }
+        if let Err(e) = self.queue.dispatch_pending(&mut self.state) {
+            log::error!("Wayland virtual input: {e}");
+        }
+    }
+
+    fn mouse_move_internal(&mut self, pos: Vec2) {
+        #[cfg(debug_assertions)]
+        log::trace!("Mouse move: {pos:?}");
+
+        let time = self.time();
+        let qh = self.queue.handle();
+        let state = &mut self.state;
+
+        if state.pointer_manager.version() >= 2 {
+            let target = state.outputs.iter_mut().find(|o| {
+                let (origin, size) = o.logical_rect();
+                let local = pos - origin;
+                local.cmpge(Vec2::ZERO).all() && local.cmplt(size).all()
+            });
+            if let Some(output) = target {
+                let (origin, size) = output.logical_rect();
+                let local = pos - origin;
+                let pointer = output.pointer.get_or_insert_with(|| {
+                    state.pointer_manager.create_virtual_pointer_with_output(
+                        Some(&state.seat),
+                        Some(&output.output),
+                        &qh,
+                        (),
+                    )
+                });
+                pointer.motion_absolute(
+                    time,
+                    local.x as u32,
+                    local.y as u32,
+                    size.x as u32,
+                    size.y as u32,
+                );
+                pointer.frame();
+                return;
+            }
+        }
+
+        if self.desktop_extent.cmple(Vec2::ZERO).any() {
+            return;
+        }
+        let pos = (pos - self.desktop_origin).max(Vec2::ZERO);
+        self.pointer.motion_absolute(
+            time,
+            pos.x as u32,
+            pos.y as u32,
+            self.desktop_extent.x as u32,
+            self.desktop_extent.y as u32,
+        );
+        self.pointer.frame();
+    }
+
+    fn send_button_internal(&self, button: u16, down: bool) {
+        let state = if down {
+            ButtonState::Pressed
+        } else {
+            ButtonState::Released
+        };
+        self.pointer.button(self.time(), button.into(), state);
+        self.pointer.frame();
+    }
+
+    fn wheel_internal(&self, delta_y: i32, delta_x: i32) {
+        // deltas are in hi-res wheel units of 120 per notch and point the opposite way
+        let time = self.time();
+        self.pointer.axis_source(AxisSource::Continuous);
+        if delta_y != 0 {
+            self.pointer
+                .axis(time, Axis::VerticalScroll, -f64::from(delta_y) / 8.0);
+        }
+        if delta_x != 0 {
+            self.pointer
+                .axis(time, Axis::HorizontalScroll, f64::from(delta_x) / 8.0);
+        }
+        self.pointer.frame();
+    }
+}
+
+impl HidProvider for WlVirtualProvider {
+    fn set_modifiers(&mut self, modifiers: u8) {
+        let changed = self.cur_modifiers ^ modifiers;
+        for i in 0..8 {
+            let m: KeyModifier = 1 << i;
+            if changed & m != 0 {
+                if let Some(vk) = MODS_TO_KEYS.get(m).into_iter().flatten().next() {
+                    self.send_key(*vk, modifiers & m != 0);
+                }
+            }
+        }
+        self.cur_modifiers = modifiers;
+    }
+    fn send_key(&self, key: VirtualKey, down: bool) {
+        #[cfg(debug_assertions)]
+        log::trace!("send_key: {key:?} {down}");
+
+        self.keyboard
+            .key(self.time(), u32::from(key as u16 - 8), u32::from(down));
+
+        // not every compositor derives the modifiers from the keys
+        let mut state = self.xkb_state.borrow_mut();
+        let direction = if down {
+            xkb::KeyDirection::Down
+        } else {
+            xkb::KeyDirection::Up
+        };
+        state.update_key(xkb::Keycode::from(key as u32), direction);
+        self.keyboard.modifiers(
+            state.serialize_mods(xkb::STATE_MODS_DEPRESSED),
+            state.serialize_mods(xkb::STATE_MODS_LATCHED),
+            state.serialize_mods(xkb::STATE_MODS_LOCKED),
+            state.serialize_layout(xkb::STATE_LAYOUT_EFFECTIVE),
+        );
+    }
+    fn set_desktop_extent(&mut self, extent: Vec2) {
+        self.desktop_extent = extent;
+    }
+    fn set_desktop_origin(&mut self, origin: Vec2) {
+        self.desktop_origin = origin;
+    }
+    fn mouse_move(&mut self, pos: Vec2) {
+        if self.current_action.pos.is_none() && self.current_action.scroll.is_none() {
+            self.current_action.pos = Some(pos);
+        }
+        self.current_action.last_requested_pos = Some(pos);
+    }
+    fn send_button(&mut self, button: u16, down: bool) {
+        if self.current_action.button.is_none() {
+            self.current_action.button = Some(MouseButtonAction { button, down });
+            self.current_action.pos = self.current_action.last_requested_pos;
+        }
+    }
+    fn wheel(&mut self, delta_y: i32, delta_x: i32) {
+        if self.current_action.scroll.is_none() {
+            self.current_action.scroll = Some(IVec2::new(delta_x, delta_y));
+            self.current_action.pos = None;
+        }
+    }
+    fn commit(&mut self) {
+        self.dispatch_events();
+
+        if let Some(pos) = self.current_action.pos.take() {
+            self.mouse_move_internal(pos);
+        }
+        if let Some(button) = self.current_action.button.take() {
+            self.send_button_internal(button.button, button.down);
+        }
+        if let Some(scroll) = self.current_action.scroll.take() {
+            self.wheel_internal(scroll.y, scroll.x);
+        }
+        if let Err(e) = self.connection.flush() {
+            log::error!("Wayland virtual input: {e}");
+        }
+    }
+}
+
+impl Drop for WlVirtualProvider {
+    fn drop(&mut self) {
+        self.keyboard.destroy();
+        self.pointer.destroy();
+        for output in &self.state.outputs {
+            output.destroy();
+        }
+        self.state.pointer_manager.destroy();
+        if let Some(manager) = &self.state.xdg_output_manager {
+            manager.destroy();
+        }
+        if self.state.seat.version() >= 5 {
+            self.state.seat.release();
+        }
+        let _ = self.connection.flush();
+    }
+}
+
+/// The virtual keyboard takes its keymap as a file descriptor
+fn keymap_to_file(keymap: &XkbKeymap) -> anyhow::Result<File> {
+    let text = keymap.keymap.get_as_string(xkb::KEYMAP_FORMAT_TEXT_V1);
+    let fd = unsafe { libc::memfd_create(c"wlx-keymap".as_ptr(), libc::MFD_CLOEXEC) };
+    if fd < 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    let mut file = unsafe { File::from_raw_fd(fd) };
+    file.write_all(text.as_bytes())?;
+    file.write_all(&[0])?;
+    Ok(file)
+}
+
+impl WlVirtualState {
+    fn bind_output(
+        &mut self,
+        registry: &WlRegistry,
+        name: u32,
+        version: u32,
+        qh: &QueueHandle<Self>,
+    ) {
+        let output: WlOutput = registry.bind(name, version.min(4), qh, name);
+        let xdg_output = self
+            .xdg_output_manager
+            .as_ref()
+            .map(|manager| manager.get_xdg_output(&output, qh, name));
+        self.outputs.push(WlVirtualOutput {
+            name,
+            output,
+            xdg_output,
+            position: IVec2::ZERO,
+            mode: IVec2::ZERO,
+            scale: 1,
+            transform: Transform::Normal,
+            logical_position: None,
+            logical_size: None,
+            pointer: None,
+        });
+    }
+}
+
+impl Dispatch<WlRegistry, GlobalListContents> for WlVirtualState {
+    fn event(
+        state: &mut Self,
+        proxy: &WlRegistry,
+        event: <WlRegistry as Proxy>::Event,
+        _data: &GlobalListContents,
+        _conn: &Connection,
+        qhandle: &QueueHandle<Self>,
+    ) {
+        match event {
+            wl_registry::Event::Global {
+                name,
+                interface,
+                version,
+            } if interface == WlOutput::interface().name => {
+                state.bind_output(proxy, name, version, qhandle);
+            }
+            wl_registry::Event::GlobalRemove { name } => {
+                if let Some(idx) = state.outputs.iter().position(|o| o.name == name) {
+                    state.outputs.remove(idx).destroy();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<WlOutput, u32> for WlVirtualState {
+    fn event(
+        state: &mut Self,
+        _proxy: &WlOutput,
+        event: <WlOutput as Proxy>::Event,
+        data: &u32,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let Some(output) = state.outputs.iter_mut().find(|o| o.name == *data) else {
+            return;
+        };
+        match event {
+            wl_output::Event::Geometry {
+                x, y, transform, ..
+            } => {
+                output.position = IVec2::new(x, y);
+                if let WEnum::Value(transform) = transform {
+                    output.transform = transform;
+                }
+            }
+            wl_output::Event::Mode {
+                flags,
+                width,
+                height,
+                ..
+            } => {
+                let current = flags
+                    .into_result()
+                    .is_ok_and(|f| f.contains(wl_output::Mode::Current));
+                if current {
+                    output.mode = IVec2::new(width, height);
+                }
+            }
+            wl_output::Event::Scale { factor } => {
+                output.scale = factor;
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ZxdgOutputV1, u32> for WlVirtualState {
+    fn event(
+        state: &mut Self,
+        _proxy: &ZxdgOutputV1,
+        event: <ZxdgOutputV1 as Proxy>::Event,
+        data: &u32,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let Some(output) = state.outputs.iter_mut().find(|o| o.name == *data) else {
+            return;
+        };
+        match event {
+            zxdg_output_v1::Event::LogicalPosition { x, y } => {
+                output.logical_position = Some(IVec2::new(x, y));
+            }
+            zxdg_output_v1::Event::LogicalSize { width, height } => {
+                output.logical_size = Some(IVec2::new(width, height));
+            }
+            _ => {}
+        }
+    }
+}
+
+delegate_noop!(WlVirtualState: ignore WlSeat);
+delegate_noop!(WlVirtualState: ZxdgOutputManagerV1);
+delegate_noop!(WlVirtualState: ZwpVirtualKeyboardManagerV1);
+delegate_noop!(WlVirtualState: ZwpVirtualKeyboardV1);
+delegate_noop!(WlVirtualState: ZwlrVirtualPointerManagerV1);
+delegate_noop!(WlVirtualState: ZwlrVirtualPointerV1);
+
+#[cfg(all(test, feature = "wayvr"))]
+mod tests {
+    use std::{
+        os::unix::net::UnixStream,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            mpsc, Arc,
+        },
+        thread,
+        time::Duration,
+    };
+
+    use glam::vec2;
+    use smithay::{
+        delegate_compositor, delegate_output, delegate_seat,
+        input::{Seat, SeatHandler, SeatState},
+        output::{Mode, Output, PhysicalProperties, Scale, Subpixel},
+        reexports::{
+            wayland_protocols_misc::zwp_virtual_keyboard_v1::server::{
+                zwp_virtual_keyboard_manager_v1::{self, ZwpVirtualKeyboardManagerV1},
+                zwp_virtual_keyboard_v1::ZwpVirtualKeyboardV1,
+            },
+            wayland_protocols_wlr::virtual_pointer::v1::server::{
+                zwlr_virtual_pointer_manager_v1::{self, ZwlrVirtualPointerManagerV1},
+                zwlr_virtual_pointer_v1::{self, ZwlrVirtualPointerV1},
+            },
+            wayland_server::{
+                backend::{ClientData, ClientId, DisconnectReason},
+                protocol::wl_surface::WlSurface,
+                Client, DataInit, Dispatch, Display, DisplayHandle, GlobalDispatch, New,
+            },
+        },
+        utils::Transform as OutputTransform,
+        wayland::{
+            compositor::{CompositorClientState, CompositorHandler, CompositorState},
+            output::{OutputHandler, OutputManagerState},
+        },
+    };
+
+    use super::*;
+    use crate::hid::get_keymap_fallback;
+
+    /// What the virtual pointers were asked to do: output name, position and extent
+    type Motion = (Option<String>, u32, u32, u32, u32);
+
+    struct Server {
+        compositor: CompositorState,
+        seat_state: SeatState<Self>,
+        motions: Vec<Motion>,
+    }
+
+    #[derive(Default)]
+    struct ClientState {
+        compositor: CompositorClientState,
+    }
+
+    impl ClientData for ClientState {
+        fn initialized(&self, _client_id: ClientId) {}
+        fn disconnected(&self, _client_id: ClientId, _reason: DisconnectReason) {}
+    }
+
+    impl CompositorHandler for Server {
+        fn compositor_state(&mut self) -> &mut CompositorState {
+            &mut self.compositor
+        }
+        fn client_compositor_state<'a>(&self, client: &'a Client) -> &'a CompositorClientState {
+            &client.get_data::<ClientState>().unwrap().compositor
+        }
+        fn commit(&mut self, _surface: &WlSurface) {}
+    }
+
+    impl SeatHandler for Server {
+        type KeyboardFocus = WlSurface;
+        type PointerFocus = WlSurface;
+        type TouchFocus = WlSurface;
+
+        fn seat_state(&mut self) -> &mut SeatState<Self> {
+            &mut self.seat_state
+        }
+    }
+
+    impl OutputHandler for Server {}
+
+    impl GlobalDispatch<ZwpVirtualKeyboardManagerV1, ()> for Server {
+        fn bind(
+            _state: &mut Self,
+            _handle: &DisplayHandle,
+            _client: &Client,
+            resource: New<ZwpVirtualKeyboardManagerV1>,
+            _global_data: &(),
+            data_init: &mut DataInit<'_, Self>,
+        ) {
+            data_init.init(resource, ());
+        }
+    }
+
+    impl Dispatch<ZwpVirtualKeyboardManagerV1, ()> for Server {
+        fn request(
+            _state: &mut Self,
+            _client: &Client,
+            _resource: &ZwpVirtualKeyboardManagerV1,
+            request: zwp_virtual_keyboard_manager_v1::Request,
+            _data: &(),
+            _dhandle: &DisplayHandle,
+            data_init: &mut DataInit<'_, Self>,
+        ) {
+            if let zwp_virtual_keyboard_manager_v1::Request::CreateVirtualKeyboard { id, .. } =
+                request
+            {
+                data_init.init(id, ());
+            }
+        }
+    }
+
+    impl Dispatch<ZwpVirtualKeyboardV1, ()> for Server {
+        fn request(
+            _state: &mut Self,
+            _client: &Client,
+            _resource: &ZwpVirtualKeyboardV1,
+            _request: <ZwpVirtualKeyboardV1 as smithay::reexports::wayland_server::Resource>::Request,
+            _data: &(),
+            _dhandle: &DisplayHandle,
+            _data_init: &mut DataInit<'_, Self>,
+        ) {
+        }
+    }
+
+    impl GlobalDispatch<ZwlrVirtualPointerManagerV1, ()> for Server {
+        fn bind(
+            _state: &mut Self,
+            _handle: &DisplayHandle,
+            _client: &Client,
+            resource: New<ZwlrVirtualPointerManagerV1>,
+            _global_data: &(),
+            data_init: &mut DataInit<'_, Self>,
+        ) {
+            data_init.init(resource, ());
+        }
+    }
+
+    impl Dispatch<ZwlrVirtualPointerManagerV1, ()> for Server {
+        fn request(
+            _state: &mut Self,
+            _client: &Client,
+            _resource: &ZwlrVirtualPointerManagerV1,
+            request: zwlr_virtual_pointer_manager_v1::Request,
+            _data: &(),
+            _dhandle: &DisplayHandle,
+            data_init: &mut DataInit<'_, Self>,
+        ) {
+            match request {
+                zwlr_virtual_pointer_manager_v1::Request::CreateVirtualPointer { id, .. } => {
+                    data_init.init(id, None);
+                }
+                zwlr_virtual_pointer_manager_v1::Request::CreateVirtualPointerWithOutput {
+                    id,
+                    output,
+                    ..
+                } => {
+                    let name = output
+                        .as_ref()
+                        .and_then(Output::from_resource)
+                        .map(|o| o.name());
+                    data_init.init(id, name);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    impl Dispatch<ZwlrVirtualPointerV1, Option<String>> for Server {
+        fn request(
+            state: &mut Self,
+            _client: &Client,
+            _resource: &ZwlrVirtualPointerV1,
+            request: zwlr_virtual_pointer_v1::Request,
+            data: &Option<String>,
+            _dhandle: &DisplayHandle,
+            _data_init: &mut DataInit<'_, Self>,
+        ) {
+            if let zwlr_virtual_pointer_v1::Request::MotionAbsolute {
+                x,
+                y,
+                x_extent,
+                y_extent,
+                ..
+            } = request
+            {
+                state.motions.push((data.clone(), x, y, x_extent, y_extent));
+            }
+        }
+    }
+
+    delegate_compositor!(Server);
+    delegate_seat!(Server);
+    delegate_output!(Server);
+
+    fn add_output(dh: &DisplayHandle, name: &str, size: (i32, i32), scale: f64, at: (i32, i32)) {
+        let output = Output::new(
+            name.into(),
+            PhysicalProperties {
+                size: (0, 0).into(),
+                subpixel: Subpixel::Unknown,
+                make: "wlx".into(),
+                model: "test".into(),
+            },
+        );
+        output.change_current_state(
+            Some(Mode {
+                size: size.into(),
+                refresh: 60_000,
+            }),
+            Some(OutputTransform::Normal),
+            Some(Scale::Fractional(scale)),
+            Some(at.into()),
+        );
+        let _global = output.create_global::<Server>(dh);
+    }
+
+    /// Runs a compositor with two outputs on its own thread until `stop` is set,
+    /// then returns the pointer motions it received.
+    fn run_server(
+        socket: UnixStream,
+        stop: Arc<AtomicBool>,
+        ready: mpsc::Sender<()>,
+    ) -> Vec<Motion> {
+        let mut display = Display::<Server>::new().unwrap();
+        let mut dh = display.handle();
+        let mut seat_state = SeatState::new();
+        let _seat: Seat<Server> = seat_state.new_wl_seat(&dh, "seat0");
+        let mut server = Server {
+            compositor: CompositorState::new::<Server>(&dh),
+            seat_state,
+            motions: Vec::new(),
+        };
+        let _outputs = OutputManagerState::new_with_xdg_output::<Server>(&dh);
+        dh.create_global::<Server, ZwpVirtualKeyboardManagerV1, _>(1, ());
+        dh.create_global::<Server, ZwlrVirtualPointerManagerV1, _>(2, ());
+
+        add_output(&dh, "LEFT", (1920, 1080), 1., (0, 0));
+        // fractional scale: wl_output reports 2, but the logical size is 2560x1440
+        add_output(&dh, "RIGHT", (3840, 2160), 1.5, (1920, 0));
+
+        dh.insert_client(socket, Arc::new(ClientState::default()))
+            .unwrap();
+        ready.send(()).unwrap();
+
+        while !stop.load(Ordering::Relaxed) {
+            display.dispatch_clients(&mut server).unwrap();
+            display.flush_clients().unwrap();
+            thread::sleep(Duration::from_millis(1));
+        }
+        server.motions
+    }
+
+    #[test]
+    fn moves_the_pointer_in_logical_coordinates() {
+        let (client_socket, server_socket) = UnixStream::pair().unwrap();
+        let stop = Arc::new(AtomicBool::new(false));
+        let (ready_tx, ready_rx) = mpsc::channel();
+        let server = {
+            let stop = stop.clone();
+            thread::spawn(move || run_server(server_socket, stop, ready_tx))
+        };
+        ready_rx.recv().unwrap();
+
+        let connection = Connection::from_socket(client_socket).unwrap();
+        let keymap = get_keymap_fallback().unwrap();
+        let mut provider = WlVirtualProvider::with_connection(connection, &keymap).unwrap();
+
+        let mut rects: Vec<_> = provider
+            .state
+            .outputs
+            .iter()
+            .map(WlVirtualOutput::logical_rect)
+            .collect();
+        rects.sort_by(|a, b| a.0.x.total_cmp(&b.0.x));
+        assert_eq!(
+            rects,
+            [
+                (vec2(0., 0.), vec2(1920., 1080.)),
+                (vec2(1920., 0.), vec2(2560., 1440.)),
+            ]
+        );
+
+        for pos in [vec2(100., 200.), vec2(3000., 1300.)] {
+            provider.mouse_move(pos);
+            provider.commit();
+        }
+        provider.connection.roundtrip().unwrap();
+        drop(provider);
+
+        stop.store(true, Ordering::Relaxed);
+        let motions = server.join().unwrap();
+        assert_eq!(
+            motions,
+            [
+                (Some("LEFT".into()), 100, 200, 1920, 1080),
+                (Some("RIGHT".into()), 1080, 1300, 2560, 1440),
+            ]
+        );
+    }
+}
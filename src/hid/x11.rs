@@ -1,3 +1,7 @@
+use glam::{IVec2, Vec2};
+use idmap::IdMap;
+use strum::IntoEnumIterator;
+use xcb::{x, xtest};
 use xkbcommon::xkb::{
     self,
     x11::{
@@ -6,14 +10,21 @@ use xkbcommon::xkb::{
     },
 };
 
-use super::XkbKeymap;
+use super::{
+    get_keymap_fallback, HidProvider, KeyModifier, MouseAction, MouseButtonAction, VirtualKey,
+    XkbKeymap, MODS_TO_KEYS, MOUSE_LEFT, MOUSE_MIDDLE, MOUSE_RIGHT,
+};
 
 pub fn get_keymap_x11() -> anyhow::Result<XkbKeymap> {
+    let (conn, _) = xcb::Connection::connect(None)?;
+    keymap_from_connection(&conn)
+}
+
+fn keymap_from_connection(conn: &xcb::Connection) -> anyhow::Result<XkbKeymap> {
     let context = xkb::Context::new(xkb::CONTEXT_NO_FLAGS);
 
-    let (conn, _) = xcb::Connection::connect(None)?;
     setup_xkb_extension(
-        &conn,
+        conn,
         MIN_MAJOR_XKB_VERSION,
         MIN_MINOR_XKB_VERSION,
         SetupXkbExtensionFlags::NoFlags,
@@ -23,13 +34,387 @@ pub fn get_keymap_x11() -> anyhow::Result<XkbKeymap> {
         &mut 0,
     );
 
-    let device_id = get_core_keyboard_device_id(&conn);
+    let device_id = get_core_keyboard_device_id(conn);
     if device_id == -1 {
         return Err(anyhow::anyhow!(
             "get_core_keyboard_device_id returned -1. Check your XKB installation."
         ));
     }
-    let keymap = keymap_new_from_device(&context, &conn, device_id, xkb::KEYMAP_COMPILE_NO_FLAGS);
+    let keymap = keymap_new_from_device(&context, conn, device_id, xkb::KEYMAP_COMPILE_NO_FLAGS);
 
     Ok(XkbKeymap { keymap })
 }
+
+/// Hi-res wheel units per wheel button click
+const WHEEL_STEP: i32 = 120;
+
+// XTest FakeInput takes the core event numbers
+const KEY_PRESS: u8 = <x::KeyPressEvent as xcb::BaseEvent>::NUMBER as u8;
+const KEY_RELEASE: u8 = <x::KeyReleaseEvent as xcb::BaseEvent>::NUMBER as u8;
+const BUTTON_PRESS: u8 = <x::ButtonPressEvent as xcb::BaseEvent>::NUMBER as u8;
+const BUTTON_RELEASE: u8 = <x::ButtonReleaseEvent as xcb::BaseEvent>::NUMBER as u8;
+const MOTION_NOTIFY: u8 = <x::MotionNotifyEvent as xcb::BaseEvent>::NUMBER as u8;
+
+/// Emulates input through the XTest extension, which needs no access to uinput.
+pub struct XTestProvider {
+    conn: xcb::Connection,
+    root: x::Window,
+    /// X server keycode of each key, which may differ from evdev-based numbering
+    keycodes: IdMap<VirtualKey, u8>,
+    desktop_origin: Vec2,
+    cur_modifiers: u8,
+    current_action: MouseAction,
+    /// Scroll that did not add up to a whole wheel click yet
+    wheel_remainder: IVec2,
+}
+
+impl XTestProvider {
+    pub fn try_new() -> anyhow::Result<Self> {
+        Self::with_display(None)
+    }
+
+    fn with_display(display: Option<&str>) -> anyhow::Result<Self> {
+        let (conn, screen) =
+            xcb::Connection::connect_with_extensions(display, &[xcb::Extension::Test], &[])?;
+        if !conn.active_extensions().any(|e| e == xcb::Extension::Test) {
+            anyhow::bail!("X server does not support XTest");
+        }
+        let version = conn.wait_for_reply(conn.send_request(&xtest::GetVersion {
+            major_version: 2,
+            minor_version: 2,
+        }))?;
+        log::debug!(
+            "XTest version {}.{}",
+            version.major_version(),
+            version.minor_version()
+        );
+
+        let root = conn
+            .get_setup()
+            .roots()
+            .nth(screen as usize)
+            .ok_or_else(|| anyhow::anyhow!("X screen {screen} not found"))?
+            .root();
+
+        let keycodes = map_keycodes(&keymap_from_connection(&conn)?);
+
+        Ok(Self {
+            conn,
+            root,
+            keycodes,
+            desktop_origin: Vec2::ZERO,
+            cur_modifiers: 0,
+            current_action: MouseAction::default(),
+            wheel_remainder: IVec2::ZERO,
+        })
+    }
+
+    fn fake_input(&self, r#type: u8, detail: u8, pos: IVec2) {
+        self.conn.send_request(&xtest::FakeInput {
+            r#type,
+            detail,
+            time: x::CURRENT_TIME,
+            root: self.root,
+            root_x: pos.x as i16,
+            root_y: pos.y as i16,
+            deviceid: 0,
+        });
+    }
+
+    fn send_button_internal(&self, button: u16, down: bool) {
+        let button = match button {
+            MOUSE_LEFT => 1,
+            MOUSE_MIDDLE => 2,
+            MOUSE_RIGHT => 3,
+            _ => return,
+        };
+        let r#type = if down { BUTTON_PRESS } else { BUTTON_RELEASE };
+        self.fake_input(r#type, button, IVec2::ZERO);
+    }
+
+    fn mouse_move_internal(&self, pos: Vec2) {
+        #[cfg(debug_assertions)]
+        log::trace!("Mouse move: {pos:?}");
+
+        let pos = (pos - self.desktop_origin).as_ivec2();
+        self.fake_input(MOTION_NOTIFY, 0, pos);
+    }
+
+    fn wheel_internal(&mut self, delta_y: i32, delta_x: i32) {
+        // X11 scrolls by clicks of buttons 4/5 (up/down) and 6/7 (left/right)
+        self.wheel_remainder += IVec2::new(delta_x, delta_y);
+        let clicks = self.wheel_remainder / WHEEL_STEP;
+        self.wheel_remainder -= clicks * WHEEL_STEP;
+
+        for (count, negative, positive) in [(clicks.y, 5, 4), (clicks.x, 6, 7)] {
+            let button = if count < 0 { negative } else { positive };
+            for _ in 0..count.abs() {
+                self.fake_input(BUTTON_PRESS, button, IVec2::ZERO);
+                self.fake_input(BUTTON_RELEASE, button, IVec2::ZERO);
+            }
+        }
+    }
+}
+
+impl HidProvider for XTestProvider {
+    fn set_modifiers(&mut self, modifiers: u8) {
+        let changed = self.cur_modifiers ^ modifiers;
+        for i in 0..8 {
+            let m: KeyModifier = 1 << i;
+            if changed & m != 0 {
+                if let Some(vk) = MODS_TO_KEYS.get(m).into_iter().flatten().next() {
+                    self.send_key(*vk, modifiers & m != 0);
+                }
+            }
+        }
+        self.cur_modifiers = modifiers;
+    }
+    fn send_key(&self, key: VirtualKey, down: bool) {
+        #[cfg(debug_assertions)]
+        log::trace!("send_key: {key:?} {down}");
+
+        let Some(keycode) = self.keycodes.get(key) else {
+            log::warn!("send_key: {key:?} is not on the X keymap");
+            return;
+        };
+        let r#type = if down { KEY_PRESS } else { KEY_RELEASE };
+        self.fake_input(r#type, *keycode, IVec2::ZERO);
+    }
+    fn set_desktop_extent(&mut self, _extent: Vec2) {}
+    fn set_desktop_origin(&mut self, origin: Vec2) {
+        self.desktop_origin = origin;
+    }
+    fn mouse_move(&mut self, pos: Vec2) {
+        if self.current_action.pos.is_none() && self.current_action.scroll.is_none() {
+            self.current_action.pos = Some(pos);
+        }
+        self.current_action.last_requested_pos = Some(pos);
+    }
+    fn send_button(&mut self, button: u16, down: bool) {
+        if self.current_action.button.is_none() {
+            self.current_action.button = Some(MouseButtonAction { button, down });
+            self.current_action.pos = self.current_action.last_requested_pos;
+        }
+    }
+    fn wheel(&mut self, delta_y: i32, delta_x: i32) {
+        if self.current_action.scroll.is_none() {
+            self.current_action.scroll = Some(IVec2::new(delta_x, delta_y));
+            self.current_action.pos = None;
+        }
+    }
+    fn commit(&mut self) {
+        if let Some(pos) = self.current_action.pos.take() {
+            self.mouse_move_internal(pos);
+        }
+        if let Some(button) = self.current_action.button.take() {
+            self.send_button_internal(button.button, button.down);
+        }
+        if let Some(scroll) = self.current_action.scroll.take() {
+            self.wheel_internal(scroll.y, scroll.x);
+        }
+        if let Err(e) = self.conn.flush() {
+            log::error!("XTest: {e}");
+        }
+    }
+}
+
+/// Finds the X keycode of each key by its XKB name, since `VirtualKey` follows evdev keycodes
+fn map_keycodes(x_keymap: &XkbKeymap) -> IdMap<VirtualKey, u8> {
+    let reference = get_keymap_fallback().ok();
+    let mut keycodes = IdMap::new();
+    for key in VirtualKey::iter() {
+        let evdev_code = xkb::Keycode::from(key as u32);
+        let x_code = reference
+            .as_ref()
+            .and_then(|r| r.keymap.key_get_name(evdev_code))
+            .map_or(Some(evdev_code), |name| x_keymap.keymap.key_by_name(name));
+        if let Some(x_code) = x_code.and_then(|c| u8::try_from(c.raw()).ok()) {
+            keycodes.insert(key, x_code);
+        }
+    }
+    keycodes
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader},
+        process::{Child, Command, Stdio},
+        time::{Duration, Instant},
+    };
+
+    use super::*;
+
+    /// A private X server, stopped on drop
+    struct Xvfb {
+        child: Child,
+        display: String,
+    }
+
+    impl Xvfb {
+        /// `None` if Xvfb is not installed or fails to start
+        fn start() -> Option<Self> {
+            let mut child = Command::new("Xvfb")
+                .args([
+                    "-displayfd",
+                    "1",
+                    "-nolisten",
+                    "tcp",
+                    "-screen",
+                    "0",
+                    "1280x720x24",
+                ])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+                .map_err(|e| eprintln!("Skipping, could not start Xvfb: {e}"))
+                .ok()?;
+            // the display number is written once the server accepts connections
+            let mut line = String::new();
+            let read = BufReader::new(child.stdout.take()?).read_line(&mut line);
+            let xvfb = Self {
+                child,
+                display: format!(":{}", line.trim()),
+            };
+            (read.is_ok_and(|n| n > 0)).then_some(xvfb)
+        }
+    }
+
+    impl Drop for Xvfb {
+        fn drop(&mut self) {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+
+    /// Swaps the keycodes of two keys in the keymap text
+    fn swap_keycodes(keymap: &XkbKeymap, a: &str, b: &str) -> XkbKeymap {
+        let code = |name: &str| keymap.keymap.key_by_name(name).unwrap().raw();
+        let (code_a, code_b) = (code(a), code(b));
+        let text = keymap
+            .keymap
+            .get_as_string(xkb::KEYMAP_FORMAT_TEXT_V1)
+            .lines()
+            .map(|line| match line.trim_start() {
+                l if l.starts_with(&format!("<{a}>")) => format!("<{a}> = {code_b};"),
+                l if l.starts_with(&format!("<{b}>")) => format!("<{b}> = {code_a};"),
+                _ => line.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let context = xkb::Context::new(xkb::CONTEXT_NO_FLAGS);
+        let keymap = xkb::Keymap::new_from_string(
+            &context,
+            text,
+            xkb::KEYMAP_FORMAT_TEXT_V1,
+            xkb::KEYMAP_COMPILE_NO_FLAGS,
+        )
+        .unwrap();
+        XkbKeymap { keymap }
+    }
+
+    #[test]
+    fn maps_keycodes_by_key_name() {
+        let evdev = get_keymap_fallback().unwrap();
+        let keycodes = map_keycodes(&swap_keycodes(&evdev, "AC01", "AC02"));
+
+        assert_eq!(keycodes.get(VirtualKey::A), Some(&(VirtualKey::S as u8)));
+        assert_eq!(keycodes.get(VirtualKey::S), Some(&(VirtualKey::A as u8)));
+        assert_eq!(keycodes.get(VirtualKey::D), Some(&(VirtualKey::D as u8)));
+    }
+
+    /// Commits and waits until the server has handled it
+    fn commit(provider: &mut XTestProvider) {
+        provider.commit();
+        let conn = &provider.conn;
+        conn.wait_for_reply(conn.send_request(&x::GetInputFocus {}))
+            .unwrap();
+    }
+
+    fn wait_for_buttons(conn: &xcb::Connection, count: usize) -> Vec<(u8, bool)> {
+        let deadline = Instant::now() + Duration::from_secs(2);
+        let mut buttons = Vec::new();
+        while buttons.len() < count && Instant::now() < deadline {
+            match conn.poll_for_event().unwrap() {
+                Some(xcb::Event::X(x::Event::ButtonPress(e))) => buttons.push((e.detail(), true)),
+                Some(xcb::Event::X(x::Event::ButtonRelease(e))) => {
+                    buttons.push((e.detail(), false));
+                }
+                Some(_) => {}
+                None => std::thread::sleep(Duration::from_millis(5)),
+            }
+        }
+        buttons
+    }
+
+    #[test]
+    fn sends_input_to_the_x_server() {
+        let Some(xvfb) = Xvfb::start() else {
+            return;
+        };
+        let mut provider = XTestProvider::with_display(Some(&xvfb.display)).unwrap();
+
+        // watch the server from a second client
+        let (conn, screen) = xcb::Connection::connect(Some(&xvfb.display)).unwrap();
+        let root = conn
+            .get_setup()
+            .roots()
+            .nth(screen as usize)
+            .unwrap()
+            .root();
+        conn.send_and_check_request(&x::ChangeWindowAttributes {
+            window: root,
+            value_list: &[x::Cw::EventMask(
+                x::EventMask::BUTTON_PRESS | x::EventMask::BUTTON_RELEASE,
+            )],
+        })
+        .unwrap();
+
+        // keys land on the keycode with the same XKB name
+        let x_keymap = keymap_from_connection(&conn).unwrap();
+        let a = x_keymap.keymap.key_by_name("AC01").unwrap().raw() as usize;
+        let is_down = |code: usize| {
+            let keys = conn
+                .wait_for_reply(conn.send_request(&x::QueryKeymap {}))
+                .unwrap();
+            keys.keys()[code / 8] & (1 << (code % 8)) != 0
+        };
+        provider.send_key(VirtualKey::A, true);
+        commit(&mut provider);
+        assert!(is_down(a));
+        provider.send_key(VirtualKey::A, false);
+        commit(&mut provider);
+        assert!(!is_down(a));
+
+        provider.set_desktop_origin(Vec2::new(100., 50.));
+        provider.mouse_move(Vec2::new(400., 250.));
+        commit(&mut provider);
+        let pointer = conn
+            .wait_for_reply(conn.send_request(&x::QueryPointer { window: root }))
+            .unwrap();
+        assert_eq!((pointer.root_x(), pointer.root_y()), (300, 200));
+
+        // half a click does nothing until the other half comes in
+        provider.wheel(60, 0);
+        commit(&mut provider);
+        provider.wheel(60, 0);
+        commit(&mut provider);
+        assert_eq!(wait_for_buttons(&conn, 2), [(4, true), (4, false)]);
+
+        // two clicks down with 10 left over, one click right with 10 left over
+        provider.wheel(-250, 130);
+        commit(&mut provider);
+        assert_eq!(
+            wait_for_buttons(&conn, 6),
+            [
+                (5, true),
+                (5, false),
+                (5, true),
+                (5, false),
+                (7, true),
+                (7, false),
+            ]
+        );
+        assert_eq!(provider.wheel_remainder, IVec2::new(10, -10));
+    }
+}
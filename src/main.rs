@@ -20,8 +20,10 @@ mod graphics;
 mod gui;
 mod hid;
 mod overlays;
+mod reminders;
 mod shaders;
 mod state;
+mod timers;
 
 #[cfg(feature = "wayvr")]
 mod config_wayvr;
@@ -82,6 +84,16 @@ struct Args {
     /// Show a desktop window of a UI panel for development
     #[arg(short, long, value_name = "UI_NAME")]
     uidev: Option<String>,
+
+    #[cfg(feature = "uidev")]
+    /// Render the --uidev panel to a PNG file and exit, without opening a window
+    #[arg(long, value_name = "FILE_PATH", requires = "uidev")]
+    uidev_png: Option<String>,
+
+    #[cfg(feature = "uidev")]
+    /// Draw pointer hit regions on the --uidev panel (toggle with H in the window)
+    #[arg(long, requires = "uidev")]
+    uidev_hit_regions: bool,
 }
 
 #[allow(clippy::unnecessary_wraps)]
@@ -115,7 +127,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     #[cfg(feature = "uidev")]
     if let Some(panel_name) = args.uidev.as_ref() {
-        crate::backend::uidev::uidev_run(panel_name.as_str())?;
+        if let Some(png_path) = args.uidev_png.as_ref() {
+            crate::backend::uidev::uidev_export_png(
+                panel_name.as_str(),
+                png_path.as_ref(),
+                args.uidev_hit_regions,
+            )?;
+        } else {
+            crate::backend::uidev::uidev_run(panel_name.as_str(), args.uidev_hit_regions)?;
+        }
         return Ok(());
     }
 
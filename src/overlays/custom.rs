@@ -4,12 +4,14 @@ use glam::Vec3A;
 
 use crate::{
     backend::overlay::{ui_transform, OverlayBackend, OverlayState},
-    config::{load_custom_ui, load_known_yaml, ConfigType},
+    config::{load_custom_ui, load_known_yaml, AStrSetExt, ConfigType},
     gui::modular::{modular_canvas, ModularUiConfig},
     state::AppState,
 };
 
 const SETTINGS_NAME: &str = "settings";
+const NOTIFICATIONS_NAME: &str = "notifications";
+const EMOJI_NAME: &str = "emoji";
 
 pub fn create_custom(
     state: &mut AppState,
@@ -17,6 +19,10 @@ pub fn create_custom(
 ) -> Option<(OverlayState, Box<dyn OverlayBackend>)> {
     let config = if &*name == SETTINGS_NAME {
         load_known_yaml::<ModularUiConfig>(ConfigType::Settings)
+    } else if &*name == NOTIFICATIONS_NAME {
+        load_known_yaml::<ModularUiConfig>(ConfigType::Notifications)
+    } else if &*name == EMOJI_NAME {
+        load_known_yaml::<ModularUiConfig>(ConfigType::Emoji)
     } else {
         match load_custom_ui(&name) {
             Ok(config) => config,
@@ -35,6 +41,8 @@ pub fn create_custom(
         }
     };
 
+    state.custom_panels.arc_set(name.clone());
+
     let state = OverlayState {
         name,
         want_visible: true,
@@ -1,42 +1,78 @@
 use std::{
+    cell::RefCell,
     collections::HashMap,
     process::{Child, Command},
+    rc::Rc,
     str::FromStr,
     sync::{Arc, LazyLock},
+    time::{Duration, Instant},
 };
 
 use crate::{
     backend::{
+        common::OverlaySelector,
         input::{InteractionHandler, PointerMode},
         overlay::{
             FrameMeta, OverlayBackend, OverlayData, OverlayRenderer, OverlayState, Positioning,
             ShouldRender,
         },
+        task::{SystemTask, TaskType},
     },
     config::{self, ConfigType},
+    config_io,
     graphics::CommandBuffers,
     gui::{
-        canvas::{builder::CanvasBuilder, control::Control, Canvas},
-        color_parse, KeyCapType,
+        canvas::{builder::CanvasBuilder, control::Control, Canvas, Rect},
+        modular::text_input::clear_orphaned_focus,
+        KeyCapType,
     },
     hid::{
         get_key_type, KeyModifier, KeyType, VirtualKey, XkbKeymap, ALT, CTRL, KEYS_TO_MODS, META,
         NUM_LOCK, SHIFT, SUPER,
     },
+    overlays::prediction::SUGGESTION_COUNT,
     state::{AppState, KeyboardFocus},
 };
-use glam::{vec2, vec3a, Affine2, Vec4};
+use glam::{vec2, vec3a, Affine2, Vec2, Vec3A, Vec4};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use vulkano::image::view::ImageView;
 
 const PIXELS_PER_UNIT: f32 = 80.;
 const BUTTON_PADDING: f32 = 4.;
+const KEY_RADIUS: f32 = 12.;
+const SUGGESTION_BAR_HEIGHT: f32 = 60.;
 const AUTO_RELEASE_MODS: [KeyModifier; 5] = [SHIFT, CTRL, ALT, SUPER, META];
 
 pub const KEYBOARD_NAME: &str = "kbd";
+/// The right half in split mode. Shown and hidden along with `KEYBOARD_NAME`.
+pub const KEYBOARD_RIGHT_NAME: &str = "kbd_right";
+/// Space between the halves of a split keyboard, in meters
+const SPLIT_GAP: f32 = 0.02;
+/// Where the halves go relative to the hands
+const SPLIT_HAND_OFFSET: Vec3A = vec3a(0., 0.1, -0.2);
+/// Extra layouts, next to keyboard.yaml in the config dir
+const LAYOUTS_DIR: &str = "keyboards";
 
 fn send_key(app: &mut AppState, key: VirtualKey, down: bool) {
+    clear_orphaned_focus(app);
+    // text typed into our own fields is often not prose, e.g. passwords
+    if down && app.session.config.keyboard_prediction && app.text_focus.is_none() {
+        track_word(app, key);
+    }
+    send_key_untracked(app, key, down);
+}
+
+/// Sends a key without following it for word suggestions
+fn send_key_untracked(app: &mut AppState, key: VirtualKey, down: bool) {
+    clear_orphaned_focus(app);
+    if let Some(focus) = app.text_focus.as_mut() {
+        if down {
+            focus.send_key(app.keymap.as_ref(), key);
+        }
+        return;
+    }
+
     match app.keyboard_focus {
         KeyboardFocus::PhysicalScreen => {
             app.hid_provider.send_key(key, down);
@@ -52,6 +88,14 @@ fn send_key(app: &mut AppState, key: VirtualKey, down: bool) {
 }
 
 fn set_modifiers(app: &mut AppState, mods: u8) {
+    app.word_predictor.set_modifiers(mods);
+
+    clear_orphaned_focus(app);
+    if let Some(focus) = app.text_focus.as_mut() {
+        focus.set_modifiers(mods);
+        return;
+    }
+
     match app.keyboard_focus {
         KeyboardFocus::PhysicalScreen => {
             app.hid_provider.set_modifiers(mods);
@@ -66,22 +110,258 @@ fn set_modifiers(app: &mut AppState, mods: u8) {
     }
 }
 
-#[allow(clippy::too_many_lines)]
+/// Follows the word being typed, for suggestions
+fn track_word(app: &mut AppState, key: VirtualKey) {
+    if KEYS_TO_MODS.get(key).is_some() {
+        return;
+    }
+
+    let selection = &app.keyboard_selection;
+    let layout = selection.layout();
+    app.word_predictor
+        .set_layout(selection.layout, &layout.name, layout.dictionary.as_deref());
+
+    let predictor = &mut app.word_predictor;
+    if key == VirtualKey::BackSpace {
+        predictor.backspace();
+        return;
+    }
+    let modifiers = predictor.modifiers();
+    if modifiers & (CTRL | ALT | SUPER) != 0 {
+        // shortcuts may move the cursor or change the text
+        predictor.reset();
+        return;
+    }
+
+    let text = app
+        .keymap
+        .as_ref()
+        .map(|k| k.text_for_key(key, modifiers))
+        .unwrap_or_default();
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_alphanumeric() || c == '\'' => predictor.push(c),
+        (Some(c), None) if c.is_whitespace() || !c.is_control() => {
+            predictor.commit(app.session.config.keyboard_prediction_learn);
+        }
+        // arrows, Escape, dead keys...
+        _ => predictor.reset(),
+    }
+}
+
+/// Replaces the current word with the suggestion in `slot`
+fn accept_suggestion(app: &mut AppState, modifiers: KeyModifier, slot: usize) {
+    let Some((erase, word)) = app.word_predictor.accept(slot) else {
+        return;
+    };
+
+    set_modifiers(app, 0);
+    for _ in 0..erase {
+        send_key(app, VirtualKey::BackSpace, true);
+        send_key(app, VirtualKey::BackSpace, false);
+    }
+    type_text(app, &format!("{word} "));
+    set_modifiers(app, modifiers);
+
+    let learn = app.session.config.keyboard_prediction_learn && app.text_focus.is_none();
+    app.word_predictor.finish_accept(&word, learn);
+}
+
+/// How to show the keyboard, set in config.yaml
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum KeyboardSplit {
+    /// One overlay with the whole layout
+    #[default]
+    Off,
+    /// Two halves, placed independently
+    Floating,
+    /// Two halves, each following a hand
+    Hands,
+}
+
+/// Which keys of the layout a keyboard overlay has
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KeyboardHalf {
+    Whole,
+    Left,
+    Right,
+}
+
+impl KeyboardHalf {
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Whole | Self::Left => KEYBOARD_NAME,
+            Self::Right => KEYBOARD_RIGHT_NAME,
+        }
+    }
+}
+
+/// Creates the keyboard overlay, or both halves in split mode
 pub fn create_keyboard<O>(
     app: &AppState,
-    mut keymap: Option<XkbKeymap>,
-) -> anyhow::Result<OverlayData<O>>
+    keymap: Option<XkbKeymap>,
+) -> anyhow::Result<Vec<OverlayData<O>>>
 where
     O: Default,
 {
+    let split = app.session.config.keyboard_split;
+    let halves: &[KeyboardHalf] = if split == KeyboardSplit::Off {
+        &[KeyboardHalf::Whole]
+    } else {
+        &[KeyboardHalf::Left, KeyboardHalf::Right]
+    };
+    let shared = Rc::new(RefCell::new(SharedKeyboard::default()));
+
+    let mut overlays = Vec::with_capacity(halves.len());
+    for &half in halves {
+        let (canvas, size) = create_keyboard_canvas(app, keymap.clone(), half)?;
+        let width = keyboard_width(app, size);
+
+        // halves sit next to each other, with a small gap
+        let side = match half {
+            KeyboardHalf::Whole => 0.,
+            KeyboardHalf::Left => -1.,
+            KeyboardHalf::Right => 1.,
+        };
+        let (positioning, spawn_point, recenter) = match (split, half) {
+            (KeyboardSplit::Hands, KeyboardHalf::Left) => (
+                Positioning::FollowHand { hand: 0, lerp: 1.0 },
+                SPLIT_HAND_OFFSET,
+                false,
+            ),
+            (KeyboardSplit::Hands, _) => (
+                Positioning::FollowHand { hand: 1, lerp: 1.0 },
+                SPLIT_HAND_OFFSET,
+                false,
+            ),
+            _ => (
+                Positioning::Anchored,
+                vec3a(side * width.mul_add(0.5, SPLIT_GAP), -0.5, 0.),
+                true,
+            ),
+        };
+
+        overlays.push(OverlayData {
+            state: OverlayState {
+                name: half.name().into(),
+                grabbable: true,
+                recenter,
+                positioning,
+                interactable: true,
+                spawn_scale: width,
+                spawn_point,
+                interaction_transform: keyboard_transform(size),
+                ..Default::default()
+            },
+            backend: Box::new(KeyboardBackend {
+                canvas,
+                half,
+                shared: shared.clone(),
+                generation: app.keyboard_selection.generation,
+            }),
+            ..Default::default()
+        });
+    }
+    Ok(overlays)
+}
+
+fn keyboard_width(app: &AppState, size: Vec2) -> f32 {
+    size.x / PIXELS_PER_UNIT * 0.05 * app.session.config.keyboard_scale
+}
+
+fn keyboard_transform(size: Vec2) -> Affine2 {
+    Affine2::from_translation(vec2(0.5, 0.5)) * Affine2::from_scale(vec2(1., -size.x / size.y))
+}
+
+/// How to type characters that are not on the keyboard layout
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize)]
+pub enum UnicodeInput {
+    /// Skip them
+    None,
+    /// Ctrl+Shift+U, the code point in hex, then Space. Understood by GTK and IBus.
+    #[default]
+    CtrlShiftU,
+}
+
+/// Types text on the focused target, through the keymap where possible.
+/// Leaves no modifiers held.
+pub fn type_text(app: &mut AppState, text: &str) {
+    clear_orphaned_focus(app);
+    if let Some(focus) = app.text_focus.as_mut() {
+        focus.insert_str(text);
+        return;
+    }
+
+    for (modifiers, vk) in strokes_for_text(app, text) {
+        set_modifiers(app, modifiers);
+        send_key(app, vk, true);
+        send_key(app, vk, false);
+    }
+    set_modifiers(app, 0);
+}
+
+/// Key presses that type the text, each with the modifiers to hold for it
+fn strokes_for_text(app: &AppState, text: &str) -> Vec<(KeyModifier, VirtualKey)> {
+    let chars = app
+        .keymap
+        .as_ref()
+        .map(XkbKeymap::char_map)
+        .unwrap_or_default();
+    let stroke = |c: char| match c {
+        '\n' => Some((0, VirtualKey::Return)),
+        c => chars.get(&c).map(|(vk, modifiers)| (*modifiers, *vk)),
+    };
+
+    let mut strokes = vec![];
+    for c in text.chars() {
+        if let Some(s) = stroke(c) {
+            strokes.push(s);
+            continue;
+        }
+        match app.session.config.unicode_input {
+            UnicodeInput::None => log::warn!("Can't type '{c}' on this keyboard layout"),
+            UnicodeInput::CtrlShiftU => {
+                strokes.push((CTRL | SHIFT, VirtualKey::U));
+                strokes.extend(format!("{:x}", c as u32).chars().filter_map(stroke));
+                strokes.push((0, VirtualKey::Space));
+            }
+        }
+    }
+    strokes
+}
+
+/// Builds the keys of the selected layout and layer that belong to `half`.
+/// Returns the canvas and its size.
+#[allow(clippy::too_many_lines)]
+fn create_keyboard_canvas(
+    app: &AppState,
+    mut keymap: Option<XkbKeymap>,
+    half: KeyboardHalf,
+) -> anyhow::Result<(Canvas<KeyboardData, KeyButtonData>, Vec2)> {
+    let layout = app.keyboard_selection.layout();
+    let keys = layout.keys(app.keyboard_selection.layer.as_deref());
+
+    // the part of the rows that this half covers, in units
+    let (start, end) = match half {
+        KeyboardHalf::Whole => (0., layout.row_size),
+        KeyboardHalf::Left => (0., layout.split_end()),
+        KeyboardHalf::Right => (layout.split_start(), layout.row_size),
+    };
+
+    // both halves get the bar, so that their rows line up and either hand can reach it
+    let bar_height = if app.session.config.keyboard_prediction {
+        SUGGESTION_BAR_HEIGHT
+    } else {
+        0.
+    };
     let size = vec2(
-        LAYOUT.row_size * PIXELS_PER_UNIT,
-        (LAYOUT.main_layout.len() as f32) * PIXELS_PER_UNIT,
+        (end - start) * PIXELS_PER_UNIT,
+        (keys.len() as f32).mul_add(PIXELS_PER_UNIT, bar_height),
     );
 
     let data = KeyboardData {
         modifiers: 0,
-        alt_modifier: match LAYOUT.alt_modifier {
+        alt_modifier: match layout.alt_modifier {
             AltModifier::Shift => SHIFT,
             AltModifier::Ctrl => CTRL,
             AltModifier::Alt => ALT,
@@ -90,6 +370,8 @@ where
             _ => 0,
         },
         processes: vec![],
+        press_color: app.theme.color_or_default("$keyboard_pressed"),
+        dead_key: None,
     };
 
     let mut canvas = CanvasBuilder::new(
@@ -100,50 +382,83 @@ where
         data,
     )?;
 
-    canvas.bg_color = color_parse("#181926").unwrap(); //safe
+    canvas.bg_color = app.theme.color_or_default("$keyboard_bg");
     canvas.panel(0., 0., size.x, size.y, 12.);
 
     canvas.font_size = 18;
-    canvas.fg_color = color_parse("#cad3f5").unwrap(); //safe
-    canvas.bg_color = color_parse("#1e2030").unwrap(); //safe
+    canvas.fg_color = app.theme.color_or_default("$keyboard_text");
+    canvas.bg_color = app.theme.color_or_default("$keyboard_key");
 
     let has_altgr = keymap
         .as_ref()
         .is_some_and(super::super::hid::XkbKeymap::has_altgr);
 
-    if !LAYOUT.auto_labels.unwrap_or(true) {
+    if !layout.auto_labels.unwrap_or(true) {
         keymap = None;
     }
 
-    let unit_size = size.x / LAYOUT.row_size;
-    let h = 2.0f32.mul_add(-BUTTON_PADDING, unit_size);
+    let unit_size = PIXELS_PER_UNIT;
+
+    if bar_height > 0. {
+        let w = size.x / SUGGESTION_COUNT as f32;
+        for slot in 0..SUGGESTION_COUNT {
+            let button = canvas.button(
+                (slot as f32).mul_add(w, BUTTON_PADDING),
+                BUTTON_PADDING,
+                2.0f32.mul_add(-BUTTON_PADDING, w),
+                2.0f32.mul_add(-BUTTON_PADDING, bar_height),
+                KEY_RADIUS,
+                "".into(),
+            );
+            button.state = Some(KeyButtonData::Suggestion { slot });
+            button.on_update = Some(suggestion_update);
+            button.on_press = Some(key_press);
+        }
+    }
 
-    for row in 0..LAYOUT.key_sizes.len() {
-        let y = unit_size.mul_add(row as f32, BUTTON_PADDING);
+    for row in 0..layout.key_sizes.len() {
+        let y = unit_size.mul_add(row as f32, BUTTON_PADDING + bar_height);
         let mut sum_size = 0f32;
 
-        for col in 0..LAYOUT.key_sizes[row].len() {
-            let my_size = LAYOUT.key_sizes[row][col];
-            let x = unit_size.mul_add(sum_size, BUTTON_PADDING);
+        let split = layout.split_point(row);
+
+        for col in 0..layout.key_sizes[row].len() {
+            let key_size = layout.key_sizes[row][col];
+            let my_size = key_size.width();
+            let in_half = match half {
+                KeyboardHalf::Whole => true,
+                KeyboardHalf::Left => col < split,
+                KeyboardHalf::Right => col >= split,
+            };
+            if !in_half {
+                sum_size += my_size;
+                continue;
+            }
+            let x = unit_size.mul_add(sum_size - start, BUTTON_PADDING);
             let w = unit_size.mul_add(my_size, -(2. * BUTTON_PADDING));
+            let (h, extra) = key_size.shape(x, y, unit_size);
 
-            if let Some(key) = LAYOUT.main_layout[row][col].as_ref() {
+            if let Some(key) = keys[row][col].as_ref() {
                 let mut label = Vec::with_capacity(2);
                 let mut maybe_state: Option<KeyButtonData> = None;
                 let mut cap_type = KeyCapType::Regular;
+                let mut levels = None;
 
                 if let Ok(vk) = VirtualKey::from_str(key) {
                     if let Some(keymap) = keymap.as_ref() {
                         match get_key_type(vk) {
                             KeyType::Symbol => {
-                                let label0 = keymap.label_for_key(vk, 0);
-                                let label1 = keymap.label_for_key(vk, SHIFT);
+                                let key_levels = keymap.levels_for_key(vk);
+                                let label0 = key_levels[0].clone();
+                                let label1 = key_levels[1].clone();
+                                let label2 = key_levels[2].clone();
+                                levels = Some(Arc::new(key_levels));
 
                                 if label0.chars().next().is_some_and(char::is_alphabetic) {
                                     label.push(label1);
                                     if has_altgr {
                                         cap_type = KeyCapType::RegularAltGr;
-                                        label.push(keymap.label_for_key(vk, META));
+                                        label.push(label2);
                                     } else {
                                         cap_type = KeyCapType::Regular;
                                     }
@@ -151,7 +466,7 @@ where
                                     label.push(label0);
                                     label.push(label1);
                                     if has_altgr {
-                                        label.push(keymap.label_for_key(vk, META));
+                                        label.push(label2);
                                         cap_type = KeyCapType::ReversedAltGr;
                                     } else {
                                         cap_type = KeyCapType::Reversed;
@@ -171,13 +486,17 @@ where
                             sticky: false,
                         });
                     } else {
-                        maybe_state = Some(KeyButtonData::Key { vk, pressed: false });
+                        maybe_state = Some(KeyButtonData::Key {
+                            vk,
+                            pressed: false,
+                            repeat_at: None,
+                        });
                     }
-                } else if let Some(macro_verbs) = LAYOUT.macros.get(key) {
+                } else if let Some(macro_verbs) = layout.macros.get(key) {
                     maybe_state = Some(KeyButtonData::Macro {
-                        verbs: key_events_for_macro(macro_verbs),
+                        verbs: parse_macro(macro_verbs).into(),
                     });
-                } else if let Some(exec_args) = LAYOUT.exec_commands.get(key) {
+                } else if let Some(exec_args) = layout.exec_commands.get(key) {
                     if exec_args.is_empty() {
                         log::error!("Keyboard: EXEC args empty for {key}");
                         continue;
@@ -191,16 +510,40 @@ where
                             release_args: iter.collect(),
                         });
                     }
+                } else if let Some(switch) = layout.layout_switches.get(key) {
+                    maybe_state = Some(KeyButtonData::Switch {
+                        switch: switch.clone(),
+                    });
                 } else {
                     log::error!("Unknown key: {key}");
                 }
 
                 if let Some(state) = maybe_state {
                     if label.is_empty() {
-                        label = LAYOUT.label_for_key(key);
+                        label = layout.label_for_key(key);
                     }
-                    let button = canvas.key_button(x, y, w, h, 12., cap_type, &label);
+                    // while Shift or AltGr is held, the first legend shows what the key types
+                    let legend_state = |slot: usize| {
+                        Some(KeyButtonData::Legend {
+                            levels: levels.clone()?,
+                            slot,
+                            idle: label.get(slot)?.as_str().into(),
+                        })
+                    };
+                    let button = canvas.key_button(
+                        x,
+                        y,
+                        w,
+                        h,
+                        extra,
+                        KEY_RADIUS,
+                        cap_type,
+                        &label,
+                        legend_state,
+                        Some(legend_update),
+                    );
                     button.state = Some(state);
+                    button.on_update = Some(key_update);
                     button.on_press = Some(key_press);
                     button.on_release = Some(key_release);
                     button.test_highlight = Some(test_highlight);
@@ -211,28 +554,7 @@ where
         }
     }
 
-    let canvas = canvas.build();
-
-    let interaction_transform = Affine2::from_translation(vec2(0.5, 0.5))
-        * Affine2::from_scale(vec2(1., -size.x as f32 / size.y as f32));
-
-    let width = LAYOUT.row_size * 0.05 * app.session.config.keyboard_scale;
-
-    Ok(OverlayData {
-        state: OverlayState {
-            name: KEYBOARD_NAME.into(),
-            grabbable: true,
-            recenter: true,
-            positioning: Positioning::Anchored,
-            interactable: true,
-            spawn_scale: width,
-            spawn_point: vec3a(0., -0.5, 0.),
-            interaction_transform,
-            ..Default::default()
-        },
-        backend: Box::new(KeyboardBackend { canvas }),
-        ..Default::default()
-    })
+    Ok((canvas.build(), size))
 }
 
 fn key_press(
@@ -242,7 +564,11 @@ fn key_press(
     mode: PointerMode,
 ) {
     match control.state.as_mut() {
-        Some(KeyButtonData::Key { vk, pressed }) => {
+        Some(KeyButtonData::Key {
+            vk,
+            pressed,
+            repeat_at,
+        }) => {
             key_click(app);
 
             data.modifiers |= match mode {
@@ -255,6 +581,15 @@ fn key_press(
 
             send_key(app, *vk, true);
             *pressed = true;
+            data.dead_key = app
+                .keymap
+                .as_ref()
+                .is_some_and(|k| k.is_dead_key(*vk, data.modifiers))
+                .then_some(*vk);
+            *repeat_at = (app.session.config.keyboard_repeat_rate > 0.).then(|| {
+                Instant::now()
+                    + Duration::from_millis(app.session.config.keyboard_repeat_delay_ms.into())
+            });
         }
         Some(KeyButtonData::Modifier { modifier, sticky }) => {
             *sticky = data.modifiers & *modifier == 0;
@@ -264,9 +599,7 @@ fn key_press(
         }
         Some(KeyButtonData::Macro { verbs }) => {
             key_click(app);
-            for (vk, press) in verbs {
-                send_key(app, *vk, *press);
-            }
+            MacroRun::new(verbs.clone(), data.modifiers).run(app);
         }
         Some(KeyButtonData::Exec { program, args, .. }) => {
             // Reap previous processes
@@ -278,6 +611,15 @@ fn key_press(
                 data.processes.push(child);
             }
         }
+        Some(KeyButtonData::Suggestion { slot }) => {
+            key_click(app);
+            accept_suggestion(app, data.modifiers, *slot);
+        }
+        Some(KeyButtonData::Switch { switch }) => {
+            key_click(app);
+            // the canvas is replaced once this press is done
+            app.keyboard_selection.apply(switch);
+        }
         None => {}
     }
 }
@@ -288,9 +630,14 @@ fn key_release(
     app: &mut AppState,
 ) {
     match control.state.as_mut() {
-        Some(KeyButtonData::Key { vk, pressed }) => {
+        Some(KeyButtonData::Key {
+            vk,
+            pressed,
+            repeat_at,
+        }) => {
             send_key(app, *vk, false);
             *pressed = false;
+            *repeat_at = None;
 
             for m in &AUTO_RELEASE_MODS {
                 if data.modifiers & *m != 0 {
@@ -324,7 +671,77 @@ fn key_release(
     }
 }
 
-static PRESS_COLOR: Vec4 = Vec4::new(198. / 255., 160. / 255., 246. / 255., 0.5);
+fn key_update(
+    control: &mut Control<KeyboardData, KeyButtonData>,
+    _data: &mut KeyboardData,
+    app: &mut AppState,
+) {
+    let Some(KeyButtonData::Key {
+        vk,
+        repeat_at: Some(repeat_at),
+        ..
+    }) = control.state.as_mut()
+    else {
+        return;
+    };
+
+    let now = Instant::now();
+    if now < *repeat_at {
+        return;
+    }
+    let rate = app.session.config.keyboard_repeat_rate;
+    if rate <= 0. {
+        return;
+    }
+
+    // the target decides what a held key does, so the word can no longer be followed
+    app.word_predictor.reset();
+    // not every target understands repeated key down events
+    send_key_untracked(app, *vk, false);
+    send_key_untracked(app, *vk, true);
+    *repeat_at = now + Duration::from_secs_f32(1. / rate);
+}
+
+fn suggestion_update(
+    control: &mut Control<KeyboardData, KeyButtonData>,
+    _data: &mut KeyboardData,
+    app: &mut AppState,
+) {
+    let Some(KeyButtonData::Suggestion { slot }) = control.state.as_ref() else {
+        return;
+    };
+    let text = app
+        .word_predictor
+        .suggestions()
+        .get(*slot)
+        .map_or("", String::as_str);
+    control.set_text(text);
+}
+
+fn legend_update(
+    control: &mut Control<KeyboardData, KeyButtonData>,
+    data: &mut KeyboardData,
+    _app: &mut AppState,
+) {
+    let Some(KeyButtonData::Legend { levels, slot, idle }) = control.state.as_ref() else {
+        return;
+    };
+
+    let level = match (data.modifiers & SHIFT != 0, data.modifiers & META != 0) {
+        (false, false) => 0,
+        (true, false) => 1,
+        (false, true) => 2,
+        (true, true) => 3,
+    };
+    let text: Arc<str> = if level == 0 {
+        idle.clone()
+    } else if *slot == 0 {
+        levels[level].as_str().into()
+    } else {
+        "".into()
+    };
+    control.set_text(&text);
+}
 
 fn test_highlight(
     control: &Control<KeyboardData, KeyButtonData>,
@@ -332,13 +749,13 @@ fn test_highlight(
     _app: &mut AppState,
 ) -> Option<Vec4> {
     let pressed = match control.state.as_ref() {
-        Some(KeyButtonData::Key { pressed, .. }) => *pressed,
+        Some(KeyButtonData::Key { vk, pressed, .. }) => *pressed || data.dead_key == Some(*vk),
         Some(KeyButtonData::Modifier { modifier, .. }) => data.modifiers & *modifier != 0,
         _ => false,
     };
 
     if pressed {
-        Some(PRESS_COLOR)
+        Some(data.press_color)
     } else {
         None
     }
@@ -348,6 +765,9 @@ struct KeyboardData {
     modifiers: KeyModifier,
     alt_modifier: KeyModifier,
     processes: Vec<Child>,
+    press_color: Vec4,
+    /// Dead key waiting for the next key
+    dead_key: Option<VirtualKey>,
 }
 
 const KEY_AUDIO_WAV: &[u8] = include_bytes!("../res/421581.wav");
@@ -362,13 +782,15 @@ enum KeyButtonData {
     Key {
         vk: VirtualKey,
         pressed: bool,
+        /// When the held key is sent again
+        repeat_at: Option<Instant>,
     },
     Modifier {
         modifier: KeyModifier,
         sticky: bool,
     },
     Macro {
-        verbs: Vec<(VirtualKey, bool)>,
+        verbs: Arc<[MacroVerb]>,
     },
     Exec {
         program: String,
@@ -376,12 +798,182 @@ enum KeyButtonData {
         release_program: Option<String>,
         release_args: Vec<String>,
     },
+    Switch {
+        switch: LayoutSwitch,
+    },
+    /// A legend of a symbol key
+    Legend {
+        /// Base, Shift, AltGr and AltGr+Shift
+        levels: Arc<[String; 4]>,
+        slot: usize,
+        /// Shown while no level modifier is held
+        idle: Arc<str>,
+    },
+    /// A word suggestion above the keys
+    Suggestion {
+        slot: usize,
+    },
 }
 
-static LAYOUT: LazyLock<Layout> = LazyLock::new(Layout::load_from_disk);
+/// keyboard.yaml first, then the files in `LAYOUTS_DIR` by name. Never empty.
+static LAYOUTS: LazyLock<Vec<Layout>> = LazyLock::new(Layout::load_all);
 
-static MACRO_REGEX: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"^([A-Za-z0-9_-]+)(?: +(UP|DOWN))?$").unwrap()); // want panic
+/// The keyboard layout and layer currently shown
+#[derive(Default)]
+pub struct KeyboardSelection {
+    layout: usize,
+    /// `None` for the main layer
+    layer: Option<Arc<str>>,
+    /// Bumped when the layout, layer or theme changed, so that every keyboard overlay rebuilds
+    generation: u64,
+}
+
+impl KeyboardSelection {
+    /// Picks the first layout made for the desktop's XKB layout, or keyboard.yaml.
+    pub fn for_keymap(keymap: Option<&XkbKeymap>) -> Self {
+        let layout = keymap
+            .and_then(XkbKeymap::layout_name)
+            .and_then(|name| {
+                let idx = LAYOUTS.iter().position(|l| l.matches_xkb(name))?;
+                log::info!("Using keyboard layout '{}' for {name}", LAYOUTS[idx].name);
+                Some(idx)
+            })
+            .unwrap_or(0);
+        Self {
+            layout,
+            layer: None,
+            generation: 0,
+        }
+    }
+
+    /// Rebuilds the keyboard on its next frame, releasing anything held on it
+    pub const fn request_rebuild(&mut self) {
+        self.generation += 1;
+    }
+
+    fn layout(&self) -> &'static Layout {
+        &LAYOUTS[self.layout.min(LAYOUTS.len() - 1)]
+    }
+
+    fn apply(&mut self, switch: &LayoutSwitch) {
+        if let Some(name) = switch.layout.as_deref() {
+            let idx = if name == NEXT_LAYOUT {
+                Some((self.layout + 1) % LAYOUTS.len())
+            } else {
+                LAYOUTS.iter().position(|l| l.name == name)
+            };
+            match idx {
+                Some(idx) => {
+                    self.layout = idx;
+                    self.layer = None;
+                }
+                None => log::error!("Unknown keyboard layout: {name}"),
+            }
+        }
+
+        if let Some(layer) = switch.layer.as_deref() {
+            // pressing the switch of the current layer goes back to the main one
+            if layer == MAIN_LAYER || self.layer.as_deref() == Some(layer) {
+                self.layer = None;
+            } else if self.layout().layers.contains_key(layer) {
+                self.layer = Some(layer.into());
+            } else {
+                log::error!("Unknown keyboard layer: {layer}");
+            }
+        }
+        self.request_rebuild();
+    }
+}
+
+const MAIN_LAYER: &str = "main";
+const NEXT_LAYOUT: &str = "next";
+
+/// Changes the layout and/or layer of the keyboard
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LayoutSwitch {
+    /// Name of a layout, or "next"
+    #[serde(default)]
+    layout: Option<Arc<str>>,
+    /// Name of a layer, or "main"
+    #[serde(default)]
+    layer: Option<Arc<str>>,
+}
+
+/// Macro verb prefix that types the rest of the verb as text
+const MACRO_TYPE: &str = "TYPE ";
+const MACRO_WAIT: &str = "WAIT ";
+const MACRO_REPEAT: &str = "REPEAT ";
+
+static MACRO_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^([A-Za-z0-9_-]+)(?: +(UP|DOWN|HOLD +(\S+)))?$").unwrap() // want panic
+});
+
+/// An entry of `key_sizes`: either just a width, or a key spanning several rows
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(untagged)]
+enum KeySize {
+    Width(f32),
+    Shaped {
+        width: f32,
+        /// Rows taken up, counting down from this one
+        #[serde(default = "def_key_rows")]
+        rows: usize,
+        /// Width of the rows below the first one, aligned to the right edge.
+        /// Makes an L-shaped key such as ISO Enter.
+        #[serde(default)]
+        lower_width: Option<f32>,
+    },
+}
+
+const fn def_key_rows() -> usize {
+    1
+}
+
+impl KeySize {
+    const fn width(self) -> f32 {
+        match self {
+            Self::Width(width) | Self::Shaped { width, .. } => width,
+        }
+    }
+
+    const fn rows(self) -> usize {
+        match self {
+            Self::Width(_) => 1,
+            Self::Shaped { rows, .. } => rows,
+        }
+    }
+
+    /// Height of the key's first part, and the rect of the lower part for L-shaped keys
+    fn shape(self, x: f32, y: f32, unit_size: f32) -> (f32, Option<Rect>) {
+        let rows = self.rows() as f32;
+        let full_height = unit_size.mul_add(rows, -(2. * BUTTON_PADDING));
+        let Self::Shaped {
+            width,
+            lower_width: Some(lower_width),
+            ..
+        } = self
+        else {
+            return (full_height, None);
+        };
+
+        let h = 2.0f32.mul_add(-BUTTON_PADDING, unit_size);
+        // the narrower part reaches into the wider one, far enough to hide
+        // the rounded corners of both at the seam
+        let overlap = 2. * KEY_RADIUS;
+        let (top_overlap, lower_overlap) = if lower_width > width {
+            (overlap, 0.)
+        } else {
+            (0., overlap)
+        };
+        let lower = Rect::new(
+            unit_size.mul_add(width - lower_width, x),
+            y + h - lower_overlap,
+            unit_size.mul_add(lower_width, -(2. * BUTTON_PADDING)),
+            full_height - h + lower_overlap,
+        );
+        (h + top_overlap, Some(lower))
+    }
+}
 
 #[derive(Debug, Default, Clone, Copy, Deserialize, Serialize)]
 #[repr(usize)]
@@ -399,47 +991,197 @@ pub enum AltModifier {
 #[allow(clippy::struct_field_names)]
 pub struct Layout {
     name: String,
+    #[serde(default)]
+    xkb_layouts: Vec<String>,
     row_size: f32,
-    key_sizes: Vec<Vec<f32>>,
+    key_sizes: Vec<Vec<KeySize>>,
     main_layout: Vec<Vec<Option<String>>>,
+    #[serde(default)]
+    layers: HashMap<String, Vec<Vec<Option<String>>>>,
     alt_modifier: AltModifier,
     exec_commands: HashMap<String, Vec<String>>,
     macros: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    layout_switches: HashMap<String, LayoutSwitch>,
     labels: HashMap<String, Vec<String>>,
+    /// Words to suggest, from dictionaries/<name>.txt in the config dir
+    #[serde(default)]
+    dictionary: Option<String>,
+    /// For each row, how many keys go to the left half of a split keyboard
+    #[serde(default)]
+    split_at: Vec<usize>,
     auto_labels: Option<bool>,
 }
 
 impl Layout {
+    fn load_all() -> Vec<Self> {
+        let mut layouts = vec![Self::load_from_disk()];
+
+        let Ok(entries) = std::fs::read_dir(config_io::get_config_root().join(LAYOUTS_DIR)) else {
+            return layouts;
+        };
+        let mut files: Vec<_> = entries
+            .filter_map(|e| e.ok()?.file_name().into_string().ok())
+            .filter(|f| f.ends_with(".yaml"))
+            .collect();
+        files.sort();
+
+        for file in files {
+            match Self::load_extra(&file) {
+                Ok(layout) => {
+                    log::info!("Loaded keyboard layout '{}' from {file}", layout.name);
+                    layouts.push(layout);
+                }
+                Err(e) => log::error!("Failed to load keyboard layout {file}: {e:?}"),
+            }
+        }
+        layouts
+    }
+
     fn load_from_disk() -> Self {
-        let mut layout = config::load_known_yaml::<Self>(ConfigType::Keyboard);
-        layout.post_load();
+        let layout = config::load_known_yaml::<Self>(ConfigType::Keyboard);
+        if let Err(e) = layout.validate() {
+            panic!("{e}");
+        }
         layout
     }
 
-    fn post_load(&mut self) {
+    fn load_extra(file: &str) -> anyhow::Result<Self> {
+        let path = format!("{LAYOUTS_DIR}/{file}");
+        let Some(yaml_data) = config_io::load(&path) else {
+            anyhow::bail!("Could not read file at {path}");
+        };
+        let layout = serde_yaml::from_str::<Self>(&yaml_data)?;
+        layout.validate()?;
+        Ok(layout)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
         for i in 0..self.key_sizes.len() {
             let row = &self.key_sizes[i];
-            let width: f32 = row.iter().sum();
-            assert!(
+            let width: f32 = row.iter().map(|k| k.width()).sum();
+            anyhow::ensure!(
                 (width - self.row_size).abs() < 0.001,
                 "Row {} has a width of {}, but the row size is {}",
                 i,
                 width,
                 self.row_size
             );
+            for (j, key) in row.iter().enumerate() {
+                anyhow::ensure!(
+                    key.rows() >= 1 && i + key.rows() <= self.key_sizes.len(),
+                    "Key {} of row {} spans {} rows, past the last row",
+                    j,
+                    i,
+                    key.rows()
+                );
+                if let KeySize::Shaped {
+                    lower_width: Some(lower_width),
+                    ..
+                } = key
+                {
+                    anyhow::ensure!(
+                        key.rows() > 1 && *lower_width > 0.,
+                        "Key {} of row {} has a lower_width, but it needs 2 or more rows and a positive width",
+                        j,
+                        i
+                    );
+                }
+            }
         }
 
-        for i in 0..self.main_layout.len() {
-            let row = &self.main_layout[i];
-            let width = row.len();
-            assert!(
-                (width == self.key_sizes[i].len()),
-                "Row {} has {} keys, needs to have {} according to key_sizes",
-                i,
-                width,
-                self.key_sizes[i].len()
+        if !self.split_at.is_empty() {
+            anyhow::ensure!(
+                self.split_at.len() == self.key_sizes.len(),
+                "split_at has {} rows, needs to have {} according to key_sizes",
+                self.split_at.len(),
+                self.key_sizes.len()
+            );
+            for (i, split) in self.split_at.iter().enumerate() {
+                anyhow::ensure!(
+                    *split <= self.key_sizes[i].len(),
+                    "split_at of row {} is {}, but the row only has {} keys",
+                    i,
+                    split,
+                    self.key_sizes[i].len()
+                );
+            }
+        }
+
+        let layers = std::iter::once((MAIN_LAYER, &self.main_layout))
+            .chain(self.layers.iter().map(|(k, v)| (k.as_str(), v)));
+        for (name, keys) in layers {
+            anyhow::ensure!(
+                keys.len() == self.key_sizes.len(),
+                "Layer {} has {} rows, needs to have {} according to key_sizes",
+                name,
+                keys.len(),
+                self.key_sizes.len()
             );
+            for i in 0..keys.len() {
+                let width = keys[i].len();
+                anyhow::ensure!(
+                    (width == self.key_sizes[i].len()),
+                    "Row {} of layer {} has {} keys, needs to have {} according to key_sizes",
+                    i,
+                    name,
+                    width,
+                    self.key_sizes[i].len()
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Keys of the row that go to the left half. Defaults to the keys that
+    /// start in the left half of the row.
+    fn split_point(&self, row: usize) -> usize {
+        if let Some(split) = self.split_at.get(row) {
+            return *split;
         }
+        let mut sum = 0.;
+        self.key_sizes[row]
+            .iter()
+            .take_while(|k| {
+                let start = sum;
+                sum += k.width();
+                start < self.row_size / 2.
+            })
+            .count()
+    }
+
+    /// Where the split of the row is, in units
+    fn split_x(&self, row: usize) -> f32 {
+        self.key_sizes[row][..self.split_point(row)]
+            .iter()
+            .map(|k| k.width())
+            .sum()
+    }
+
+    /// Width of the left half: up to the split that is furthest right
+    fn split_end(&self) -> f32 {
+        (0..self.key_sizes.len())
+            .map(|row| self.split_x(row))
+            .fold(0., f32::max)
+    }
+
+    /// Start of the right half: from the split that is furthest left
+    fn split_start(&self) -> f32 {
+        (0..self.key_sizes.len())
+            .map(|row| self.split_x(row))
+            .fold(self.row_size, f32::min)
+    }
+
+    fn keys(&self, layer: Option<&str>) -> &Vec<Vec<Option<String>>> {
+        layer
+            .and_then(|l| self.layers.get(l))
+            .unwrap_or(&self.main_layout)
+    }
+
+    fn matches_xkb(&self, layout_name: &str) -> bool {
+        self.xkb_layouts
+            .iter()
+            .any(|l| l.eq_ignore_ascii_case(layout_name))
     }
 
     fn label_for_key(&self, key: &str) -> Vec<String> {
@@ -470,38 +1212,145 @@ impl Layout {
     }
 }
 
-fn key_events_for_macro(macro_verbs: &Vec<String>) -> Vec<(VirtualKey, bool)> {
+/// One step of a keyboard macro
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MacroVerb {
+    Key(VirtualKey, bool),
+    Type(String),
+    Wait(Duration),
+}
+
+/// A macro in progress. Everything up to the next WAIT is sent at once,
+/// the rest is resumed by a task once the wait is over.
+pub struct MacroRun {
+    verbs: Arc<[MacroVerb]>,
+    next: usize,
+    /// Modifiers to go back to after typing text
+    modifiers: KeyModifier,
+}
+
+impl MacroRun {
+    pub const fn new(verbs: Arc<[MacroVerb]>, modifiers: KeyModifier) -> Self {
+        Self {
+            verbs,
+            next: 0,
+            modifiers,
+        }
+    }
+
+    pub fn run(mut self, app: &mut AppState) {
+        let verbs = self.verbs.clone();
+        while let Some(verb) = verbs.get(self.next) {
+            self.next += 1;
+            match verb {
+                MacroVerb::Key(vk, press) => send_key(app, *vk, *press),
+                MacroVerb::Type(text) => {
+                    type_text(app, text);
+                    set_modifiers(app, self.modifiers);
+                }
+                MacroVerb::Wait(duration) => {
+                    let at = Instant::now() + *duration;
+                    app.tasks
+                        .enqueue_at(TaskType::System(SystemTask::Macro(self)), at);
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Accepts `50ms`, `1.5s` or a plain number of milliseconds
+fn parse_macro_duration(text: &str) -> Option<Duration> {
+    let text = text.trim();
+    let (number, millis_per_unit) = text
+        .strip_suffix("ms")
+        .map(|ms| (ms, 1.))
+        .or_else(|| text.strip_suffix('s').map(|s| (s, 1000.)))
+        .unwrap_or((text, 1.));
+    let millis = number.trim().parse::<f64>().ok()? * millis_per_unit;
+    if !millis.is_finite() || millis < 0. {
+        return None;
+    }
+    // round to whole microseconds so "50ms" is exactly 50ms
+    Some(Duration::from_micros((millis * 1000.).round() as u64))
+}
+
+pub fn parse_macro<S: AsRef<str>>(macro_verbs: &[S]) -> Vec<MacroVerb> {
     let mut key_events = vec![];
+    // Start of the verbs that the next REPEAT applies to
+    let mut block_start = 0;
     for verb in macro_verbs {
-        if let Some(caps) = MACRO_REGEX.captures(verb) {
+        let verb = verb.as_ref();
+        if let Some(text) = verb.strip_prefix(MACRO_TYPE) {
+            key_events.push(MacroVerb::Type(text.to_string()));
+        } else if let Some(arg) = verb.strip_prefix(MACRO_WAIT) {
+            let Some(duration) = parse_macro_duration(arg) else {
+                log::error!("Invalid duration in macro: {verb}");
+                return vec![];
+            };
+            key_events.push(MacroVerb::Wait(duration));
+        } else if let Some(arg) = verb.strip_prefix(MACRO_REPEAT) {
+            let Ok(count) = arg.trim().parse::<usize>() else {
+                log::error!("Invalid count in macro: {verb}");
+                return vec![];
+            };
+            if count == 0 {
+                key_events.truncate(block_start);
+            }
+            let block_end = key_events.len();
+            for _ in 1..count {
+                key_events.extend_from_within(block_start..block_end);
+            }
+            block_start = key_events.len();
+        } else if let Some(caps) = MACRO_REGEX.captures(verb) {
             if let Ok(virtual_key) = VirtualKey::from_str(&caps[1]) {
                 if let Some(state) = caps.get(2) {
                     if state.as_str() == "UP" {
-                        key_events.push((virtual_key, false));
+                        key_events.push(MacroVerb::Key(virtual_key, false));
                     } else if state.as_str() == "DOWN" {
-                        key_events.push((virtual_key, true));
+                        key_events.push(MacroVerb::Key(virtual_key, true));
+                    } else if let Some(duration) =
+                        caps.get(3).and_then(|d| parse_macro_duration(d.as_str()))
+                    {
+                        key_events.push(MacroVerb::Key(virtual_key, true));
+                        key_events.push(MacroVerb::Wait(duration));
+                        key_events.push(MacroVerb::Key(virtual_key, false));
                     } else {
                         log::error!(
-                            "Unknown key state in macro: {}, looking for UP or DOWN.",
+                            "Unknown key state in macro: {}, looking for UP, DOWN or HOLD <duration>.",
                             state.as_str()
                         );
                         return vec![];
                     }
                 } else {
-                    key_events.push((virtual_key, true));
-                    key_events.push((virtual_key, false));
+                    key_events.push(MacroVerb::Key(virtual_key, true));
+                    key_events.push(MacroVerb::Key(virtual_key, false));
                 }
             } else {
                 log::error!("Unknown virtual key: {}", &caps[1]);
                 return vec![];
             }
+        } else {
+            log::error!("Unknown macro verb: {verb}");
+            return vec![];
         }
     }
     key_events
 }
 
+/// What the halves of a split keyboard have in common
+#[derive(Default)]
+struct SharedKeyboard {
+    modifiers: KeyModifier,
+    dead_key: Option<VirtualKey>,
+}
+
 struct KeyboardBackend {
     canvas: Canvas<KeyboardData, KeyButtonData>,
+    half: KeyboardHalf,
+    shared: Rc<RefCell<SharedKeyboard>>,
+    /// `KeyboardSelection::generation` that the canvas was built for
+    generation: u64,
 }
 
 impl OverlayBackend for KeyboardBackend {
@@ -520,7 +1369,9 @@ impl InteractionHandler for KeyboardBackend {
         hit: &crate::backend::input::PointerHit,
         pressed: bool,
     ) {
+        self.load_shared();
         self.canvas.on_pointer(app, hit, pressed);
+        self.store_shared();
     }
     fn on_scroll(
         &mut self,
@@ -529,7 +1380,9 @@ impl InteractionHandler for KeyboardBackend {
         delta_y: f32,
         delta_x: f32,
     ) {
+        self.load_shared();
         self.canvas.on_scroll(app, hit, delta_y, delta_x);
+        self.store_shared();
     }
     fn on_left(&mut self, app: &mut AppState, pointer: usize) {
         self.canvas.on_left(app, pointer);
@@ -543,12 +1396,86 @@ impl InteractionHandler for KeyboardBackend {
     }
 }
 
+impl KeyboardBackend {
+    fn rebuild(&mut self, app: &mut AppState) -> anyhow::Result<()> {
+        let (canvas, size) = create_keyboard_canvas(app, app.keymap.clone(), self.half)?;
+        // keys held on the old canvas would never see their release
+        self.canvas.release_all(app);
+        let old_extent = self.canvas.frame_meta().map(|m| m.extent);
+        self.canvas = canvas;
+        let resized = self.canvas.frame_meta().map(|m| m.extent) != old_extent;
+        self.shared.borrow_mut().modifiers = 0;
+        set_modifiers(app, 0);
+
+        let width = keyboard_width(app, size);
+        app.tasks.enqueue(TaskType::Overlay(
+            OverlaySelector::Name(self.half.name().into()),
+            Box::new(move |app, o| {
+                o.interaction_transform = keyboard_transform(size);
+                if resized {
+                    // keep where the user put it, but at the new width
+                    let ratio = width / o.spawn_scale;
+                    if let Some(saved) = o.saved_transform.as_mut() {
+                        saved.matrix3 *= ratio;
+                    }
+                    o.spawn_scale = width;
+                    o.reset(app, false);
+                }
+                o.dirty = true;
+            }),
+        ));
+        Ok(())
+    }
+
+    /// Picks up what the other half changed
+    fn load_shared(&mut self) {
+        let shared = self.shared.borrow();
+        let data = self.canvas.data_mut();
+        data.modifiers = shared.modifiers;
+        data.dead_key = shared.dead_key;
+    }
+
+    fn store_shared(&mut self) {
+        let mut shared = self.shared.borrow_mut();
+        let data = self.canvas.data_mut();
+        shared.modifiers = data.modifiers;
+        shared.dead_key = data.dead_key;
+    }
+
+    /// Shows or hides the right half along with the left one
+    fn sync_right_half(&self, app: &mut AppState, visible: bool) {
+        if self.half != KeyboardHalf::Left {
+            return;
+        }
+        app.tasks.enqueue(TaskType::Overlay(
+            OverlaySelector::Name(KEYBOARD_RIGHT_NAME.into()),
+            Box::new(move |app, o| {
+                if o.want_visible == visible {
+                    return;
+                }
+                o.want_visible = visible;
+                if visible && o.recenter {
+                    o.reset(app, false);
+                }
+            }),
+        ));
+    }
+}
+
 impl OverlayRenderer for KeyboardBackend {
     fn init(&mut self, app: &mut AppState) -> anyhow::Result<()> {
         self.canvas.init(app)
     }
     fn should_render(&mut self, app: &mut AppState) -> anyhow::Result<ShouldRender> {
-        self.canvas.should_render(app)
+        let generation = app.keyboard_selection.generation;
+        if self.generation != generation {
+            self.rebuild(app)?;
+            self.generation = generation;
+        }
+        self.load_shared();
+        let result = self.canvas.should_render(app);
+        self.store_shared();
+        result
     }
     fn render(
         &mut self,
@@ -564,10 +1491,94 @@ impl OverlayRenderer for KeyboardBackend {
     }
     fn pause(&mut self, app: &mut AppState) -> anyhow::Result<()> {
         self.canvas.data_mut().modifiers = 0;
+        self.shared.borrow_mut().modifiers = 0;
         set_modifiers(app, 0);
+        app.text_focus = None;
+        app.word_predictor.save_learned();
+        self.sync_right_half(app, false);
         self.canvas.pause(app)
     }
     fn resume(&mut self, app: &mut AppState) -> anyhow::Result<()> {
+        self.sync_right_half(app, true);
         self.canvas.resume(app)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use MacroVerb::{Key, Type, Wait};
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_macro_duration("50"), Some(Duration::from_millis(50)));
+        assert_eq!(
+            parse_macro_duration("50ms"),
+            Some(Duration::from_millis(50))
+        );
+        assert_eq!(
+            parse_macro_duration(" 1.5s "),
+            Some(Duration::from_millis(1500))
+        );
+        assert_eq!(parse_macro_duration("abc"), None);
+        assert_eq!(parse_macro_duration("-1s"), None);
+        assert_eq!(parse_macro_duration("infs"), None);
+    }
+
+    #[test]
+    fn parses_keys_and_text() {
+        assert_eq!(
+            parse_macro(&["LShift DOWN", "A", "LShift UP", "TYPE hi there"]),
+            vec![
+                Key(VirtualKey::LShift, true),
+                Key(VirtualKey::A, true),
+                Key(VirtualKey::A, false),
+                Key(VirtualKey::LShift, false),
+                Type("hi there".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_wait_and_hold() {
+        assert_eq!(
+            parse_macro(&["A HOLD 200ms", "WAIT 1s"]),
+            vec![
+                Key(VirtualKey::A, true),
+                Wait(Duration::from_millis(200)),
+                Key(VirtualKey::A, false),
+                Wait(Duration::from_secs(1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn repeats_the_block_since_the_last_repeat() {
+        assert_eq!(
+            parse_macro(&["TYPE a", "REPEAT 3", "TYPE b", "REPEAT 2", "TYPE c"]),
+            vec![
+                Type("a".into()),
+                Type("a".into()),
+                Type("a".into()),
+                Type("b".into()),
+                Type("b".into()),
+                Type("c".into()),
+            ]
+        );
+        assert_eq!(
+            parse_macro(&["TYPE a", "REPEAT 1", "TYPE b", "REPEAT 0"]),
+            vec![Type("a".into())]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_macros() {
+        assert!(parse_macro(&["WAIT soon"]).is_empty());
+        assert!(parse_macro(&["TYPE a", "REPEAT -1"]).is_empty());
+        assert!(parse_macro(&["A HOLD"]).is_empty());
+        assert!(parse_macro(&["A HOLD forever"]).is_empty());
+        assert!(parse_macro(&["NotAKey"]).is_empty());
+        assert!(parse_macro(&["A SIDEWAYS"]).is_empty());
+    }
+}
@@ -3,6 +3,7 @@ pub mod custom;
 pub mod keyboard;
 #[cfg(feature = "wayland")]
 pub mod mirror;
+pub mod prediction;
 pub mod screen;
 pub mod toast;
 pub mod watch;
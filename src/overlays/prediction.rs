@@ -0,0 +1,375 @@
+use std::{collections::BTreeMap, fmt::Write, path::PathBuf};
+
+use crate::{config_io, hid::KeyModifier};
+
+/// Where dictionaries and learned words are kept, inside the config dir
+const DICTIONARIES_DIR: &str = "dictionaries";
+pub const SUGGESTION_COUNT: usize = 5;
+const MAX_WORD_LEN: usize = 32;
+
+/// Dictionaries that ship with the app, used when the config dir has none by that name
+const BUILTIN_DICTIONARIES: [(&str, &str); 1] = [("en", include_str!("../res/words_en.txt"))];
+
+/// Suggests completions for the word being typed on the VR keyboard.
+/// Words come from the dictionary of the current layout, plus words learned from typing.
+#[derive(Default)]
+pub struct WordPredictor {
+    /// Index of the keyboard layout the words were loaded for
+    layout: Option<usize>,
+    layout_name: String,
+    words: BTreeMap<String, u32>,
+    /// Times each word was typed, saved per layout
+    learned: BTreeMap<String, u32>,
+    /// How much a learned word weighs against dictionary counts, per use
+    learned_weight: u32,
+    /// Learned words changed since the last save
+    learned_dirty: bool,
+    word: String,
+    suggestions: Vec<String>,
+    /// Ignores sent keys while a suggestion is typed
+    paused: bool,
+    /// Modifiers currently held on the keyboard
+    modifiers: KeyModifier,
+}
+
+impl WordPredictor {
+    /// Loads the words for a keyboard layout, unless already loaded
+    pub fn set_layout(&mut self, layout: usize, name: &str, dictionary: Option<&str>) {
+        if self.layout == Some(layout) {
+            return;
+        }
+
+        self.save_learned();
+        self.layout = Some(layout);
+        self.layout_name = name.to_string();
+        self.words = dictionary.map(load_dictionary).unwrap_or_default();
+        let max_count = self.words.values().copied().max().unwrap_or(0);
+        self.learned_weight = (max_count / 50).max(1);
+
+        self.learned = std::fs::read_to_string(learned_path(name))
+            .map(|text| parse_words(&text))
+            .unwrap_or_default();
+        for (word, count) in &self.learned {
+            *self.words.entry(word.clone()).or_default() += count * self.learned_weight;
+        }
+
+        log::info!(
+            "Loaded {} words for keyboard layout '{name}'",
+            self.words.len()
+        );
+        self.reset();
+    }
+
+    pub const fn set_modifiers(&mut self, modifiers: KeyModifier) {
+        self.modifiers = modifiers;
+    }
+
+    pub const fn modifiers(&self) -> KeyModifier {
+        self.modifiers
+    }
+
+    pub fn suggestions(&self) -> &[String] {
+        &self.suggestions
+    }
+
+    /// Adds a typed character to the current word
+    pub fn push(&mut self, c: char) {
+        if self.paused || self.word.chars().count() >= MAX_WORD_LEN {
+            return;
+        }
+        self.word.push(c);
+        self.update();
+    }
+
+    pub fn backspace(&mut self) {
+        if self.paused {
+            return;
+        }
+        self.word.pop();
+        self.update();
+    }
+
+    /// The word was finished by a space or punctuation
+    pub fn commit(&mut self, learn: bool) {
+        if self.paused {
+            return;
+        }
+        let word = std::mem::take(&mut self.word);
+        if learn {
+            self.learn(&word);
+        }
+        self.update();
+    }
+
+    /// The cursor may have moved, so the current word is unknown
+    pub fn reset(&mut self) {
+        if self.paused {
+            return;
+        }
+        self.word.clear();
+        self.update();
+    }
+
+    /// Starts replacing the current word with a suggestion.
+    /// Returns how many characters to erase and the word to type,
+    /// then call `finish_accept` once typed.
+    pub fn accept(&mut self, slot: usize) -> Option<(usize, String)> {
+        let suggestion = self.suggestions.get(slot)?.clone();
+        let erase = self.word.chars().count();
+        self.word.clear();
+        self.update();
+        self.paused = true;
+        Some((erase, suggestion))
+    }
+
+    pub fn finish_accept(&mut self, word: &str, learn: bool) {
+        self.paused = false;
+        if learn {
+            self.learn(word);
+        }
+    }
+
+    fn learn(&mut self, word: &str) {
+        let word = word.to_lowercase();
+        let len = word.chars().count();
+        if !(2..=MAX_WORD_LEN).contains(&len) || !word.chars().any(char::is_alphabetic) {
+            return;
+        }
+
+        *self.learned.entry(word.clone()).or_default() += 1;
+        *self.words.entry(word).or_default() += self.learned_weight;
+        self.learned_dirty = true;
+    }
+
+    /// Writes learned words to disk, if any changed
+    pub fn save_learned(&mut self) {
+        if !self.learned_dirty {
+            return;
+        }
+        self.learned_dirty = false;
+        let path = learned_path(&self.layout_name);
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let mut text = String::new();
+        for (word, count) in &self.learned {
+            let _ = writeln!(text, "{word} {count}");
+        }
+        if let Err(e) = std::fs::write(&path, text) {
+            log::warn!("Failed to save learned words: {e:?}");
+        }
+    }
+
+    fn update(&mut self) {
+        self.suggestions.clear();
+        if self.word.is_empty() {
+            return;
+        }
+
+        let prefix = self.word.to_lowercase();
+        let mut matches: Vec<(&String, u32)> = self
+            .words
+            .range(prefix.clone()..)
+            .take_while(|(w, _)| w.starts_with(&prefix))
+            .filter(|(w, _)| **w != prefix)
+            .map(|(w, c)| (w, *c))
+            .collect();
+        // most used first, stable so ties stay alphabetical
+        matches.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+
+        let upper = self.word.chars().count() > 1 && !self.word.chars().any(char::is_lowercase);
+        let capital = self.word.chars().next().is_some_and(char::is_uppercase);
+        self.suggestions = matches
+            .into_iter()
+            .take(SUGGESTION_COUNT)
+            .map(|(w, _)| match_case(w, upper, capital))
+            .collect();
+    }
+}
+
+impl Drop for WordPredictor {
+    fn drop(&mut self) {
+        self.save_learned();
+    }
+}
+
+fn match_case(word: &str, upper: bool, capital: bool) -> String {
+    if upper {
+        return word.to_uppercase();
+    }
+    if capital {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            return first.to_uppercase().chain(chars).collect();
+        }
+    }
+    word.to_string()
+}
+
+fn learned_path(layout_name: &str) -> PathBuf {
+    let file = layout_name.replace(['/', '\\'], "_");
+    config_io::get_config_root()
+        .join(DICTIONARIES_DIR)
+        .join(format!("learned_{file}.txt"))
+}
+
+/// Reads `dictionaries/<name>.txt` from the config dir, or the built-in one of that name
+fn load_dictionary(name: &str) -> BTreeMap<String, u32> {
+    let path = format!("{DICTIONARIES_DIR}/{name}.txt");
+    if let Some(text) = config_io::load(&path) {
+        return parse_words(&text);
+    }
+    if let Some((_, text)) = BUILTIN_DICTIONARIES.iter().find(|(n, _)| *n == name) {
+        return parse_words(text);
+    }
+    log::warn!("Keyboard dictionary not found: {path}");
+    BTreeMap::new()
+}
+
+/// One word per line, optionally followed by how common it is.
+/// Without counts, words near the top of the file rank higher.
+fn parse_words(text: &str) -> BTreeMap<String, u32> {
+    let lines: Vec<&str> = text
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .collect();
+
+    let mut words = BTreeMap::new();
+    for (i, line) in lines.iter().enumerate() {
+        let mut parts = line.split_whitespace();
+        let Some(word) = parts.next() else {
+            continue;
+        };
+        let count = parts
+            .next()
+            .and_then(|c| c.parse::<u32>().ok())
+            .unwrap_or((lines.len() - i) as u32);
+        *words.entry(word.to_lowercase()).or_default() += count;
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn predictor(dictionary: &str) -> WordPredictor {
+        let mut predictor = WordPredictor::default();
+        predictor.words = parse_words(dictionary);
+        predictor.learned_weight = 1;
+        predictor
+    }
+
+    fn type_word(predictor: &mut WordPredictor, word: &str) {
+        for c in word.chars() {
+            predictor.push(c);
+        }
+    }
+
+    #[test]
+    fn parses_words() {
+        let words = parse_words("# comment\n\nthe 100\nHello 5\nhello 2\n  world  \n");
+        assert_eq!(words.get("the"), Some(&100));
+        assert_eq!(words.get("hello"), Some(&7));
+        // without a count, ranked by how far from the bottom it is
+        assert_eq!(words.get("world"), Some(&1));
+        assert_eq!(words.len(), 3);
+    }
+
+    #[test]
+    fn unranked_words_keep_file_order() {
+        let words = parse_words("first\nsecond\nthird");
+        assert!(words["first"] > words["second"]);
+        assert!(words["second"] > words["third"]);
+    }
+
+    #[test]
+    fn matches_case() {
+        assert_eq!(match_case("hello", false, false), "hello");
+        assert_eq!(match_case("hello", false, true), "Hello");
+        assert_eq!(match_case("hello", true, true), "HELLO");
+        assert_eq!(match_case("élan", false, true), "Élan");
+        assert_eq!(match_case("", false, true), "");
+    }
+
+    #[test]
+    fn suggests_by_count() {
+        let mut p = predictor("help 5\nhello 10\nhelium 1\nhe 50\nworld 100");
+        type_word(&mut p, "he");
+        // the typed word itself is not suggested
+        assert_eq!(p.suggestions(), ["hello", "help", "helium"]);
+
+        type_word(&mut p, "lp");
+        assert!(p.suggestions().is_empty());
+
+        p.backspace();
+        assert_eq!(p.suggestions(), ["hello", "help", "helium"]);
+
+        p.reset();
+        assert!(p.suggestions().is_empty());
+    }
+
+    #[test]
+    fn suggestions_follow_the_typed_case() {
+        let mut p = predictor("hello 1");
+        type_word(&mut p, "He");
+        assert_eq!(p.suggestions(), ["Hello"]);
+        p.reset();
+        type_word(&mut p, "HE");
+        assert_eq!(p.suggestions(), ["HELLO"]);
+        p.reset();
+        // a single capital is not caps lock
+        type_word(&mut p, "H");
+        assert_eq!(p.suggestions(), ["Hello"]);
+    }
+
+    #[test]
+    fn limits_suggestions() {
+        let mut p = predictor("aa\nab\nac\nad\nae\naf\nag");
+        p.push('a');
+        assert_eq!(p.suggestions().len(), SUGGESTION_COUNT);
+    }
+
+    #[test]
+    fn learns_committed_words() {
+        let mut p = predictor("tea 2\nteam 1");
+        type_word(&mut p, "Teapot");
+        p.commit(true);
+        assert!(p.suggestions().is_empty());
+        assert_eq!(p.learned.get("teapot"), Some(&1));
+
+        // single letters, numbers and words typed without learning are skipped
+        type_word(&mut p, "a");
+        p.commit(true);
+        type_word(&mut p, "1234");
+        p.commit(true);
+        type_word(&mut p, "teammate");
+        p.commit(false);
+        assert_eq!(p.learned.len(), 1);
+
+        type_word(&mut p, "tea");
+        assert_eq!(p.suggestions(), ["team", "teapot"]);
+        p.reset();
+        p.learn("teapot");
+        p.learn("teapot");
+        type_word(&mut p, "tea");
+        assert_eq!(p.suggestions(), ["teapot", "team"]);
+
+        // keep the test from writing to the config dir
+        p.learned_dirty = false;
+    }
+
+    #[test]
+    fn accepting_pauses_tracking() {
+        let mut p = predictor("hello 1");
+        type_word(&mut p, "hel");
+        assert_eq!(p.accept(0), Some((3, "hello".to_string())));
+        // the suggestion being typed is not tracked
+        type_word(&mut p, "hello ");
+        assert!(p.suggestions().is_empty());
+        p.finish_accept("hello", false);
+        p.push('h');
+        assert_eq!(p.suggestions(), ["hello"]);
+    }
+}
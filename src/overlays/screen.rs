@@ -55,7 +55,7 @@ use crate::{
             SplitOverlayBackend,
         },
     },
-    config::{def_pw_tokens, GeneralConfig, PwTokenMap},
+    config::{def_pw_tokens, AStrSetExt, GeneralConfig, PwTokenMap},
     graphics::{fourcc_to_vk, CommandBuffers, WlxGraphics, WlxPipeline, WlxUploadsBuffer},
     hid::{MOUSE_LEFT, MOUSE_MIDDLE, MOUSE_RIGHT},
     state::{AppSession, AppState, KeyboardFocus, ScreenMeta},
@@ -84,10 +84,13 @@ fn set_next_move(millis_from_now: u64) {
 }
 
 pub struct ScreenInteractionHandler {
+    name: Arc<str>,
     mouse_transform: Affine2,
+    /// Bit per pointer that is touching the screen
+    touching: u32,
 }
 impl ScreenInteractionHandler {
-    fn new(pos: Vec2, size: Vec2, transform: Transform) -> Self {
+    fn new(name: Arc<str>, pos: Vec2, size: Vec2, transform: Transform) -> Self {
         let transform = match transform {
             Transform::_90 | Transform::Flipped90 => Affine2::from_cols(
                 vec2(0., size.y),
@@ -108,7 +111,20 @@ impl ScreenInteractionHandler {
         };
 
         Self {
+            name,
             mouse_transform: transform,
+            touching: 0,
+        }
+    }
+
+    fn is_touching(&self, pointer: usize) -> bool {
+        pointer < u32::BITS as usize && self.touching & (1 << pointer) != 0
+    }
+
+    fn release_touch(&mut self, app: &mut AppState, pointer: usize) {
+        if self.is_touching(pointer) {
+            self.touching &= !(1 << pointer);
+            app.hid_provider.touch(pointer, None);
         }
     }
 }
@@ -117,6 +133,11 @@ impl InteractionHandler for ScreenInteractionHandler {
     fn on_hover(&mut self, app: &mut AppState, hit: &PointerHit) -> Option<Haptics> {
         #[cfg(debug_assertions)]
         log::trace!("Hover: {:?}", hit.uv);
+        if self.is_touching(hit.pointer) {
+            let pos = self.mouse_transform.transform_point2(hit.uv);
+            app.hid_provider.touch(hit.pointer, Some(pos));
+            return None;
+        }
         if can_move()
             && (!app.session.config.focus_follows_mouse_mode
                 || app.input_state.pointers[hit.pointer].now.move_mouse)
@@ -128,6 +149,22 @@ impl InteractionHandler for ScreenInteractionHandler {
         None
     }
     fn on_pointer(&mut self, app: &mut AppState, hit: &PointerHit, pressed: bool) {
+        if !pressed && self.is_touching(hit.pointer) {
+            self.release_touch(app, hit.pointer);
+            return;
+        }
+        if pressed
+            && matches!(hit.mode, PointerMode::Left)
+            && hit.pointer < u32::BITS as usize
+            && app.hid_provider.has_touch()
+            && app.session.config.touch_screens.arc_get(&self.name)
+        {
+            self.touching |= 1 << hit.pointer;
+            let pos = self.mouse_transform.transform_point2(hit.uv);
+            app.hid_provider.touch(hit.pointer, Some(pos));
+            return;
+        }
+
         let btn = match hit.mode {
             PointerMode::Right => MOUSE_RIGHT,
             PointerMode::Middle => MOUSE_MIDDLE,
@@ -161,7 +198,9 @@ impl InteractionHandler for ScreenInteractionHandler {
         app.hid_provider
             .wheel((delta_y * 64.) as i32, (delta_x * 64.) as i32);
     }
-    fn on_left(&mut self, _app: &mut AppState, _hand: usize) {}
+    fn on_left(&mut self, app: &mut AppState, hand: usize) {
+        self.release_touch(app, hand);
+    }
 }
 
 #[derive(Clone)]
@@ -681,11 +720,12 @@ pub fn create_screen_renderer_wl(
 }
 
 pub fn create_screen_interaction(
+    name: Arc<str>,
     logical_pos: Vec2,
     logical_size: Vec2,
     transform: Transform,
 ) -> ScreenInteractionHandler {
-    ScreenInteractionHandler::new(logical_pos, logical_size, transform)
+    ScreenInteractionHandler::new(name, logical_pos, logical_size, transform)
 }
 
 fn create_screen_state(
@@ -816,7 +856,12 @@ pub fn create_screens_wayland(wl: &mut WlxClientAlias, app: &mut AppState) -> Sc
             let logical_pos = vec2(output.logical_pos.0 as f32, output.logical_pos.1 as f32);
             let logical_size = vec2(output.logical_size.0 as f32, output.logical_size.1 as f32);
             let transform = output.transform.into();
-            let interaction = create_screen_interaction(logical_pos, logical_size, transform);
+            let interaction = create_screen_interaction(
+                output.name.clone(),
+                logical_pos,
+                logical_size,
+                transform,
+            );
 
             let state =
                 create_screen_state(output.name.clone(), output.size, transform, &app.session);
@@ -916,6 +961,7 @@ pub fn create_screens_x11pw(app: &mut AppState) -> anyhow::Result<ScreenCreateDa
 
             let size = (m.monitor.width(), m.monitor.height());
             let interaction = create_screen_interaction(
+                m.name.clone(),
                 vec2(m.monitor.x() as f32, m.monitor.y() as f32),
                 vec2(m.monitor.width() as f32, m.monitor.height() as f32),
                 Transform::Normal,
@@ -982,6 +1028,7 @@ pub fn create_screens_xshm(app: &mut AppState) -> anyhow::Result<ScreenCreateDat
             );
 
             let interaction = create_screen_interaction(
+                s.name.clone(),
                 vec2(s.monitor.x() as f32, s.monitor.y() as f32),
                 vec2(size.0 as f32, size.1 as f32),
                 Transform::Normal,
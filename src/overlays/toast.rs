@@ -1,30 +1,50 @@
 use std::{
+    collections::VecDeque,
     f32::consts::PI,
-    ops::Add,
-    sync::{Arc, LazyLock},
-    time::Instant,
+    sync::Arc,
+    time::{Duration, Instant},
 };
 
-use glam::{vec3a, Quat};
+use glam::{vec3a, Quat, Vec3A, Vec4};
 use idmap_derive::IntegerId;
 use serde::{Deserialize, Serialize};
 
 use crate::{
     backend::{
         common::OverlaySelector,
-        overlay::{OverlayBackend, OverlayState, Positioning, Z_ORDER_TOAST},
+        notifications::Urgency,
+        overlay::{ui_transform, OverlayBackend, OverlayState, Positioning, Z_ORDER_TOAST},
         task::TaskType,
     },
-    gui::{canvas::builder::CanvasBuilder, color_parse},
+    gui::{
+        canvas::builder::CanvasBuilder,
+        icon::IconSource,
+        modular::{
+            button::{modular_button_init, ButtonAction, ButtonData},
+            ModularData,
+        },
+    },
     state::{AppState, LeftRight},
 };
 
 const FONT_SIZE: isize = 16;
 const PADDING: (f32, f32) = (25., 7.);
 const PIXELS_TO_METERS: f32 = 1. / 2000.;
-static TOAST_NAME: LazyLock<Arc<str>> = LazyLock::new(|| "toast".into());
+const ROW_HEIGHT: f32 = 40.;
+const TITLE_HEIGHT: f32 = 30.;
+const ICON_SIZE: f32 = 48.;
+const ICON_SPACING: f32 = 10.;
+const BUTTON_PADDING: f32 = 16.;
+const BUTTON_SPACING: f32 = 6.;
+const OVERFLOW_HEIGHT: f32 = 36.;
+const PROGRESS_HEIGHT: f32 = 8.;
+const PROGRESS_ROW: f32 = 16.;
+/// Vertical space between stacked toasts, in meters
+const STACK_GAP: f32 = 0.005;
+const FADE_SECS: f32 = 0.5;
+const FADE_STEPS: u32 = 10;
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum DisplayMethod {
     Hide,
     Center,
@@ -37,6 +57,10 @@ pub enum ToastTopic {
     DesktopNotification,
     XSNotification,
     IpdChange,
+    Timer,
+    Reminder,
+    OvrtNotification,
+    OvrtWristNotification,
 }
 
 pub struct Toast {
@@ -46,6 +70,21 @@ pub struct Toast {
     pub timeout: f32,
     pub sound: bool,
     pub topic: ToastTopic,
+    /// Buttons under the body. Makes the toast interactable.
+    pub actions: Vec<(Arc<str>, ButtonAction)>,
+    pub icon: Option<IconSource>,
+    /// Overrides the display method of the topic
+    pub display: Option<DisplayMethod>,
+    /// Played instead of the notification sound
+    pub custom_sound: Option<&'static [u8]>,
+    /// Height of the content area in pixels, excluding buttons
+    pub min_height: f32,
+    /// Critical toasts stay until dismissed, low ones are shown on a single line
+    pub urgency: Urgency,
+    /// Fraction from 0 to 1 to show as a progress bar
+    pub progress: Option<f32>,
+    /// A toast replaces any shown or queued toast with the same tag, in place
+    pub tag: Option<Arc<str>>,
 }
 
 #[allow(dead_code)]
@@ -58,6 +97,14 @@ impl Toast {
             timeout: 3.0,
             sound: false,
             topic,
+            actions: Vec::new(),
+            icon: None,
+            display: None,
+            custom_sound: None,
+            min_height: 0.,
+            urgency: Urgency::Normal,
+            progress: None,
+            tag: None,
         }
     }
     pub const fn with_timeout(mut self, timeout: f32) -> Self {
@@ -72,66 +119,131 @@ impl Toast {
         self.sound = sound;
         self
     }
+    pub fn with_action(mut self, label: Arc<str>, action: ButtonAction) -> Self {
+        self.actions.push((label, action));
+        self
+    }
+    pub const fn with_display(mut self, display: DisplayMethod) -> Self {
+        self.display = Some(display);
+        self
+    }
+    pub const fn with_custom_sound(mut self, wav: &'static [u8]) -> Self {
+        self.custom_sound = Some(wav);
+        self
+    }
+    pub const fn with_min_height(mut self, height: f32) -> Self {
+        self.min_height = height;
+        self
+    }
+    pub const fn with_urgency(mut self, urgency: Urgency) -> Self {
+        self.urgency = urgency;
+        self
+    }
+    pub const fn with_progress(mut self, progress: f32) -> Self {
+        self.progress = Some(progress.clamp(0., 1.));
+        self
+    }
+    pub fn with_tag(mut self, tag: Arc<str>) -> Self {
+        self.tag = Some(tag);
+        self
+    }
+    pub fn with_icon(mut self, icon: IconSource) -> Self {
+        self.icon = Some(icon);
+        self
+    }
+    /// Where the toast goes: its own display method, or else the one of its topic
+    pub fn display_method(&self, app: &AppState) -> DisplayMethod {
+        self.display.unwrap_or_else(|| {
+            app.session
+                .toast_topics
+                .get(self.topic)
+                .copied()
+                .unwrap_or(DisplayMethod::Hide)
+        })
+    }
     pub fn submit(self, app: &mut AppState) {
         self.submit_at(app, Instant::now());
     }
     pub fn submit_at(self, app: &mut AppState, instant: Instant) {
-        let selector = OverlaySelector::Name(TOAST_NAME.clone());
-
-        let destroy_at = instant.add(std::time::Duration::from_secs_f32(self.timeout));
-
         let has_sound = self.sound && app.session.config.notifications_sound_enabled;
         if has_sound {
-            app.audio.play(app.toast_sound);
+            app.audio.play(self.custom_sound.unwrap_or(app.toast_sound));
         }
 
-        // drop any toast that was created before us.
-        // (DropOverlay only drops overlays that were
-        // created before current frame)
-        app.tasks
-            .enqueue_at(TaskType::DropOverlay(selector.clone()), instant);
+        // every toast is its own overlay, so that several can be shown at once
+        let key = app.toasts.alloc_key();
+        let name: Arc<str> = format!("toast-{key}").into();
 
-        // CreateOverlay only creates the overlay if
-        // the selector doesn't exist yet, so in case
-        // multiple toasts are submitted for the same
-        // frame, only the first one gets created
         app.tasks.enqueue_at(
             TaskType::CreateOverlay(
-                selector,
-                Box::new(move |app| {
-                    let mut maybe_toast = new_toast(self, app);
-                    if let Some((state, _)) = maybe_toast.as_mut() {
-                        state.auto_movement(app);
-                        app.tasks.enqueue_at(
-                            // at timeout, drop the overlay by ID instead
-                            // in order to avoid dropping any newer toasts
-                            TaskType::DropOverlay(OverlaySelector::Id(state.id)),
-                            destroy_at,
-                        );
-                    }
-                    maybe_toast
-                }),
+                OverlaySelector::Name(name.clone()),
+                Box::new(move |app| show_toast(self, key, name, app)),
             ),
             instant,
         );
     }
 }
 
-fn new_toast(toast: Toast, app: &mut AppState) -> Option<(OverlayState, Box<dyn OverlayBackend>)> {
-    let current_method = app
-        .session
-        .toast_topics
-        .get(toast.topic)
-        .copied()
-        .unwrap_or(DisplayMethod::Hide);
+struct ShownToast {
+    key: u64,
+    name: Arc<str>,
+    method: DisplayMethod,
+    tag: Option<Arc<str>>,
+    /// In meters
+    height: f32,
+}
+
+/// Toasts currently on screen, stacked upwards from the spawn point of their display method.
+/// Toasts that do not fit wait in a queue until another one goes away.
+#[derive(Default)]
+pub struct ToastStack {
+    next_key: u64,
+    shown: Vec<ShownToast>,
+    queued: VecDeque<(DisplayMethod, Toast)>,
+    /// The "+N more" indicator overlay of each display method
+    overflow: Vec<(DisplayMethod, Arc<str>)>,
+}
+
+impl ToastStack {
+    const fn alloc_key(&mut self) -> u64 {
+        self.next_key += 1;
+        self.next_key
+    }
+
+    fn find_tagged(&self, method: DisplayMethod, tag: Option<&Arc<str>>) -> Option<usize> {
+        let tag = tag?;
+        self.shown
+            .iter()
+            .position(|t| t.method == method && t.tag.as_ref() == Some(tag))
+    }
+
+    fn count(&self, method: DisplayMethod) -> usize {
+        self.shown.iter().filter(|t| t.method == method).count()
+    }
 
-    let (spawn_point, spawn_rotation, positioning) = match current_method {
-        DisplayMethod::Hide => return None,
-        DisplayMethod::Center => (
+    /// Distance from the spawn point to the top of the stack
+    fn height(&self, method: DisplayMethod) -> f32 {
+        self.height_below(method, self.shown.len())
+    }
+
+    /// Distance from the spawn point to the top of the toasts before `idx`
+    fn height_below(&self, method: DisplayMethod, idx: usize) -> f32 {
+        self.shown[..idx]
+            .iter()
+            .filter(|t| t.method == method)
+            .map(|t| t.height + STACK_GAP)
+            .sum()
+    }
+}
+
+fn spawn_for(method: DisplayMethod, app: &AppState) -> Option<(Vec3A, Quat, Positioning)> {
+    match method {
+        DisplayMethod::Hide => None,
+        DisplayMethod::Center => Some((
             vec3a(0., -0.2, -0.5),
             Quat::IDENTITY,
             Positioning::FollowHead { lerp: 0.1 },
-        ),
+        )),
         DisplayMethod::Watch => {
             let mut watch_pos = app.session.config.watch_pos + vec3a(-0.005, -0.05, 0.02);
             let mut watch_rot = app.session.config.watch_rot;
@@ -143,22 +255,270 @@ fn new_toast(toast: Toast, app: &mut AppState) -> Option<(OverlayState, Box<dyn
                     Positioning::FollowHand { hand: 1, lerp: 1.0 }
                 }
             };
-            (watch_pos, watch_rot, relative_to)
+            Some((watch_pos, watch_rot, relative_to))
         }
+    }
+}
+
+/// Spawn point of something whose bottom edge is `bottom` meters above the stack origin
+fn stack_point(base: Vec3A, rotation: Quat, bottom: f32, height: f32) -> Vec3A {
+    base + rotation * Vec3A::Y * (bottom + height * 0.5)
+}
+
+fn show_toast(
+    toast: Toast,
+    key: u64,
+    name: Arc<str>,
+    app: &mut AppState,
+) -> Option<(OverlayState, Box<dyn OverlayBackend>)> {
+    let method = toast.display_method(app);
+    let (base, rotation, positioning) = spawn_for(method, app)?;
+
+    if let Some(tag) = &toast.tag {
+        if let Some(queued) = app
+            .toasts
+            .queued
+            .iter_mut()
+            .find(|(m, t)| *m == method && t.tag.as_ref() == Some(tag))
+        {
+            queued.1 = toast;
+            return None;
+        }
+    }
+
+    let replaced = app.toasts.find_tagged(method, toast.tag.as_ref());
+    if replaced.is_none() && app.toasts.count(method) >= app.session.config.toast_max_stack.max(1) {
+        app.toasts.queued.push_back((method, toast));
+        update_overflow(app, method);
+        return None;
+    }
+
+    let timeout = toast.timeout;
+    let opacity = toast.opacity;
+    let sticky = toast.urgency == Urgency::Critical;
+    let tag = toast.tag.clone();
+    let (mut state, backend, height) = new_toast(toast, key, name.clone(), app)?;
+
+    // a replacement takes the place of the old toast in the stack
+    let idx = replaced.map_or(app.toasts.shown.len(), |idx| {
+        let old = app.toasts.shown.remove(idx);
+        app.tasks
+            .enqueue(TaskType::DropOverlay(OverlaySelector::Name(old.name)));
+        idx
+    });
+
+    let bottom = app.toasts.height_below(method, idx);
+    state.spawn_point = stack_point(base, rotation, bottom, height);
+    state.spawn_rotation = rotation;
+    state.positioning = positioning;
+    state.alpha = opacity;
+    state.auto_movement(app);
+
+    app.toasts.shown.insert(
+        idx,
+        ShownToast {
+            key,
+            name: name.clone(),
+            method,
+            tag,
+            height,
+        },
+    );
+    if !sticky {
+        schedule_expiry(app, key, name, timeout, opacity);
+    }
+    if replaced.is_some() {
+        relayout(app, method);
+    }
+    update_overflow(app, method);
+
+    Some((state, backend))
+}
+
+fn schedule_expiry(app: &mut AppState, key: u64, name: Arc<str>, timeout: f32, opacity: f32) {
+    // timeouts come from configs and remote clients, which may send anything
+    let timeout = if timeout.is_finite() {
+        timeout.clamp(0., 3600.)
+    } else {
+        0.
     };
+    let destroy_at = Instant::now() + Duration::from_secs_f32(timeout);
+    let fade = FADE_SECS.min(timeout * 0.5);
 
-    let title = if toast.title.is_empty() {
+    for step in 1..=FADE_STEPS {
+        let progress = step as f32 / FADE_STEPS as f32;
+        app.tasks.enqueue_at(
+            TaskType::Overlay(
+                OverlaySelector::Name(name.clone()),
+                Box::new(move |_, o| {
+                    o.alpha = opacity * (1. - progress);
+                    o.dirty = true;
+                }),
+            ),
+            destroy_at - Duration::from_secs_f32(fade * (1. - progress)),
+        );
+    }
+
+    app.tasks.enqueue_at(
+        TaskType::Overlay(
+            OverlaySelector::Name(name),
+            Box::new(move |app, _| dismiss_toast(app, key)),
+        ),
+        destroy_at,
+    );
+}
+
+/// Removes a toast, moves the ones above it down and shows the next queued toast.
+pub fn dismiss_toast(app: &mut AppState, key: u64) {
+    let Some(idx) = app.toasts.shown.iter().position(|t| t.key == key) else {
+        return;
+    };
+    let shown = app.toasts.shown.remove(idx);
+    app.tasks
+        .enqueue(TaskType::DropOverlay(OverlaySelector::Name(shown.name)));
+    relayout(app, shown.method);
+
+    if let Some(idx) = app
+        .toasts
+        .queued
+        .iter()
+        .position(|(m, _)| *m == shown.method)
+    {
+        // the sound was played when it first came in
+        if let Some((_, toast)) = app.toasts.queued.remove(idx) {
+            toast.with_sound(false).submit(app);
+        }
+    }
+    update_overflow(app, shown.method);
+}
+
+/// Moves the toasts of a stack to their places after one was removed or resized.
+fn relayout(app: &mut AppState, method: DisplayMethod) {
+    let Some((base, rotation, _)) = spawn_for(method, app) else {
+        return;
+    };
+    let mut bottom = 0.;
+    for t in app.toasts.shown.iter().filter(|t| t.method == method) {
+        let point = stack_point(base, rotation, bottom, t.height);
+        app.tasks.enqueue(TaskType::Overlay(
+            OverlaySelector::Name(t.name.clone()),
+            Box::new(move |_, o| o.spawn_point = point),
+        ));
+        bottom += t.height + STACK_GAP;
+    }
+}
+
+/// Recreates the "+N more" indicator on top of the stack.
+fn update_overflow(app: &mut AppState, method: DisplayMethod) {
+    if let Some(idx) = app.toasts.overflow.iter().position(|(m, _)| *m == method) {
+        let (_, name) = app.toasts.overflow.remove(idx);
+        app.tasks
+            .enqueue(TaskType::DropOverlay(OverlaySelector::Name(name)));
+    }
+
+    let count = app
+        .toasts
+        .queued
+        .iter()
+        .filter(|(m, _)| *m == method)
+        .count();
+    if count == 0 {
+        return;
+    }
+
+    let name: Arc<str> = format!("toast-more-{}", app.toasts.alloc_key()).into();
+    let bottom = app.toasts.height(method);
+    app.toasts.overflow.push((method, name.clone()));
+
+    app.tasks.enqueue(TaskType::CreateOverlay(
+        OverlaySelector::Name(name.clone()),
+        Box::new(move |app| {
+            let (base, rotation, positioning) = spawn_for(method, app)?;
+            let text: Arc<str> = format!("+{count} more").into();
+            let (w, _) = app
+                .fc
+                .get_text_size(&text, FONT_SIZE, app.graphics.clone())
+                .ok()?;
+            let size = (PADDING.0.mul_add(2., w), OVERFLOW_HEIGHT);
+
+            let mut canvas = CanvasBuilder::<(), ()>::new(
+                size.0 as _,
+                size.1 as _,
+                app.graphics.clone(),
+                app.graphics.native_format,
+                (),
+            )
+            .ok()?;
+            canvas.font_size = FONT_SIZE;
+            canvas.fg_color = app.theme.color_or_default("$toast_text");
+            canvas.bg_color = app.theme.color_or_default("$toast_bg");
+            canvas.panel(0., 0., size.0, size.1, 12.);
+            canvas.label_centered(0., 0., size.0, size.1, 12., text);
+
+            let mut state = OverlayState {
+                name,
+                want_visible: true,
+                spawn_scale: size.0 * PIXELS_TO_METERS,
+                spawn_rotation: rotation,
+                spawn_point: stack_point(base, rotation, bottom, size.1 * PIXELS_TO_METERS),
+                z_order: Z_ORDER_TOAST,
+                positioning,
+                ..Default::default()
+            };
+            state.auto_movement(app);
+            Some((state, Box::new(canvas.build()) as Box<dyn OverlayBackend>))
+        }),
+    ));
+}
+
+fn new_toast(
+    toast: Toast,
+    key: u64,
+    name: Arc<str>,
+    app: &mut AppState,
+) -> Option<(OverlayState, Box<dyn OverlayBackend>, f32)> {
+    let mut title = if toast.title.is_empty() {
         "Notification".into()
     } else {
         toast.title
     };
+    let mut body = toast.body;
+    if toast.urgency == Urgency::Low && !body.is_empty() {
+        title = format!("{title}: {body}").into();
+        body = "".into();
+    }
+
+    let critical = toast.urgency == Urgency::Critical;
+    let (title_fg, title_bg) = if critical {
+        ("$toast_critical_text", "$toast_critical_bg")
+    } else {
+        ("$toast_title_text", "$toast_title_bg")
+    };
+
+    let mut actions = toast.actions;
+    if critical && actions.is_empty() {
+        // nothing else would close it
+        actions.push(("Dismiss".into(), ButtonAction::DismissToast { key }));
+    }
+
+    let icon = toast.icon.and_then(|icon| {
+        icon.load(&app.session.config.notification_icon_theme)
+            .and_then(|image| Ok((image.upload(app)?, image.width, image.height)))
+            .map_err(|e| log::debug!("Could not load toast icon: {e:?}"))
+            .ok()
+    });
+    let icon_space = if icon.is_some() {
+        ICON_SIZE + ICON_SPACING
+    } else {
+        0.
+    };
 
-    let mut size = if toast.body.is_empty() {
+    let mut size = if body.is_empty() {
         let (w, h) = app
             .fc
             .get_text_size(&title, FONT_SIZE, app.graphics.clone())
             .ok()?;
-        (w, h + 20.)
+        (w + icon_space, (h + 20.).max(icon_space))
     } else {
         let (w0, _) = app
             .fc
@@ -166,15 +526,42 @@ fn new_toast(toast: Toast, app: &mut AppState) -> Option<(OverlayState, Box<dyn
             .ok()?;
         let (w1, h1) = app
             .fc
-            .get_text_size(&toast.body, FONT_SIZE, app.graphics.clone())
+            .get_text_size(&body, FONT_SIZE, app.graphics.clone())
             .ok()?;
-        (w0.max(w1), h1 + 50.)
+        (
+            w0.max(w1 + icon_space),
+            (h1 + 50.).max(TITLE_HEIGHT + icon_space + ICON_SPACING),
+        )
     };
 
+    let button_widths = actions
+        .iter()
+        .map(|(label, _)| {
+            app.fc
+                .get_text_size(label, FONT_SIZE, app.graphics.clone())
+                .map(|(w, _)| BUTTON_PADDING.mul_add(2., w))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()
+        .ok()?;
+    let buttons_width = button_widths.iter().sum::<f32>()
+        + BUTTON_SPACING * button_widths.len().saturating_sub(1) as f32;
+    size.0 = size.0.max(buttons_width);
+
+    size.1 = size.1.max(toast.min_height);
+    let progress_y = size.1;
+    if toast.progress.is_some() {
+        size.1 += PROGRESS_ROW;
+    }
+    let content_height = size.1;
+    let interactable = !actions.is_empty();
+    if interactable {
+        size.1 += ROW_HEIGHT;
+    }
+
     let og_width = size.0;
     size.0 += PADDING.0 * 2.;
 
-    let mut canvas = CanvasBuilder::<(), ()>::new(
+    let mut canvas = CanvasBuilder::<(), ModularData>::new(
         size.0 as _,
         size.1 as _,
         app.graphics.clone(),
@@ -184,34 +571,97 @@ fn new_toast(toast: Toast, app: &mut AppState) -> Option<(OverlayState, Box<dyn
     .ok()?;
 
     canvas.font_size = FONT_SIZE;
-    canvas.fg_color = color_parse("#cad3f5").unwrap(); // want panic
-    canvas.bg_color = color_parse("#1e2030").unwrap(); // want panic
+    if critical && body.is_empty() {
+        canvas.fg_color = app.theme.color_or_default(title_fg);
+        canvas.bg_color = app.theme.color_or_default(title_bg);
+    } else {
+        canvas.fg_color = app.theme.color_or_default("$toast_text");
+        canvas.bg_color = app.theme.color_or_default("$toast_bg");
+    }
     canvas.panel(0., 0., size.0, size.1, 16.);
 
-    if toast.body.is_empty() {
-        canvas.label_centered(PADDING.0, 0., og_width, size.1, 16., title);
+    // icon on the left, centered in the area under the title bar
+    let icon_area_y = if body.is_empty() { 0. } else { TITLE_HEIGHT };
+    if let Some((view, w, h)) = icon {
+        let scale = ICON_SIZE / w.max(h) as f32;
+        let (w, h) = (w as f32 * scale, h as f32 * scale);
+        let sprite = canvas.sprite(
+            PADDING.0 + (ICON_SIZE - w) * 0.5,
+            icon_area_y + (content_height - icon_area_y - h) * 0.5,
+            w,
+            h,
+        );
+        sprite.fg_color = Vec4::ONE;
+        sprite.set_sprite(view);
+    }
+
+    if let Some(progress) = toast.progress {
+        let fg_color = canvas.fg_color;
+        let (x, y, w) = (
+            PADDING.0 + icon_space,
+            progress_y + (PROGRESS_ROW - PROGRESS_HEIGHT) * 0.5,
+            og_width - icon_space,
+        );
+        canvas.bg_color = app.theme.color_or_default("$toast_title_bg");
+        canvas.panel(x, y, w, PROGRESS_HEIGHT, PROGRESS_HEIGHT * 0.5);
+        if progress > 0. {
+            canvas.bg_color = app.theme.color_or_default("$toast_progress");
+            canvas.panel(x, y, w * progress, PROGRESS_HEIGHT, PROGRESS_HEIGHT * 0.5);
+        }
+        canvas.fg_color = fg_color;
+    }
+
+    if body.is_empty() {
+        canvas.label_centered(
+            PADDING.0 + icon_space,
+            0.,
+            og_width - icon_space,
+            progress_y,
+            16.,
+            title,
+        );
     } else {
-        canvas.label(PADDING.0, 54., og_width, size.1 - 54., 3., toast.body);
+        canvas.label(
+            PADDING.0 + icon_space,
+            54.,
+            og_width - icon_space,
+            progress_y - 54.,
+            3.,
+            body,
+        );
 
-        canvas.fg_color = color_parse("#b8c0e0").unwrap(); // want panic
-        canvas.bg_color = color_parse("#24273a").unwrap(); // want panic
-        canvas.panel(0., 0., size.0, 30., 16.);
+        canvas.fg_color = app.theme.color_or_default(title_fg);
+        canvas.bg_color = app.theme.color_or_default(title_bg);
+        canvas.panel(0., 0., size.0, TITLE_HEIGHT, 16.);
         canvas.label_centered(PADDING.0, 16., og_width, FONT_SIZE as f32 + 2., 16., title);
     }
 
+    // any response also closes the toast
+    let dismiss = ButtonAction::DismissToast { key };
+
+    canvas.fg_color = app.theme.color_or_default(title_fg);
+    canvas.bg_color = app.theme.color_or_default(title_bg);
+    let row_y = content_height;
+
+    let mut button_x = PADDING.0;
+    for ((label, action), w) in actions.into_iter().zip(button_widths) {
+        let button = canvas.button(button_x, row_y, w, ROW_HEIGHT - BUTTON_SPACING, 6., label);
+        modular_button_init(button, &ButtonData::on_click(vec![action, dismiss.clone()]));
+        button_x += w + BUTTON_SPACING;
+    }
+
     let state = OverlayState {
-        name: TOAST_NAME.clone(),
+        name,
         want_visible: true,
+        interactable,
         spawn_scale: size.0 * PIXELS_TO_METERS,
-        spawn_rotation,
-        spawn_point,
         z_order: Z_ORDER_TOAST,
-        positioning,
+        interaction_transform: ui_transform([size.0 as _, size.1 as _]),
         ..Default::default()
     };
     let backend = Box::new(canvas.build());
 
-    Some((state, backend))
+    Some((state, backend, size.1 * PIXELS_TO_METERS))
 }
 
 fn msg_err(app: &mut AppState, message: &str) {
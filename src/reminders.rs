@@ -0,0 +1,506 @@
+// Reminders from `~/.config/wlxoverlay/reminders.yaml`, see the README for the format.
+
+use std::{
+    path::Path,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Datelike, Local, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::Deserialize;
+
+use crate::{
+    backend::task::{SystemTask, TaskContainer, TaskType},
+    config_io,
+    gui::modular::button::ButtonAction,
+    overlays::toast::{Toast, ToastTopic},
+    state::AppState,
+};
+
+const REMINDERS_FILE: &str = "reminders.yaml";
+
+const SNOOZE_MINUTES: f32 = 10.;
+
+/// Tolerance between the monotonic clock used by tasks and the wall clock
+const DUE_SLACK_SECS: i64 = 1;
+
+const fn def_lead_minutes() -> u32 {
+    5
+}
+
+#[derive(Deserialize, Default)]
+struct ScheduleFile {
+    #[serde(default)]
+    reminders: Vec<ReminderConfig>,
+    #[serde(default)]
+    calendars: Vec<Arc<str>>,
+    #[serde(default = "def_lead_minutes")]
+    calendar_lead_minutes: u32,
+}
+
+#[derive(Deserialize)]
+struct ReminderConfig {
+    title: Arc<str>,
+    body: Option<Arc<str>>,
+    every: Option<f32>,
+    at: Option<Arc<str>>,
+    #[serde(default)]
+    days: Vec<Arc<str>>,
+}
+
+#[derive(Deserialize, Clone)]
+pub enum ReminderAction {
+    /// Show the most recent reminder again after the given time
+    Snooze {
+        minutes: f32,
+    },
+    Dismiss,
+}
+
+enum Schedule {
+    Every(chrono::Duration),
+    Daily { time: NaiveTime, days: Vec<Weekday> },
+    Once(DateTime<Local>),
+}
+
+impl Schedule {
+    fn next_after(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        match self {
+            Self::Every(interval) => Some(after + *interval),
+            Self::Daily { time, days } => (0..8).find_map(|day| {
+                let date = after.date_naive() + chrono::Duration::days(day);
+                if !days.is_empty() && !days.contains(&date.weekday()) {
+                    return None;
+                }
+                Local
+                    .from_local_datetime(&date.and_time(*time))
+                    .earliest()
+                    .filter(|t| *t > after)
+            }),
+            Self::Once(at) => Some(*at).filter(|at| *at > after),
+        }
+    }
+}
+
+pub struct Reminder {
+    pub title: Arc<str>,
+    pub body: Arc<str>,
+    schedule: Schedule,
+    next: Option<DateTime<Local>>,
+    snoozed: Option<DateTime<Local>>,
+}
+
+impl Reminder {
+    /// The earliest time this reminder will show up.
+    pub fn due(&self) -> Option<DateTime<Local>> {
+        match (self.next, self.snoozed) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Reminders {
+    items: Vec<Reminder>,
+    /// The reminder that was shown last and not yet dismissed
+    active: Option<usize>,
+}
+
+impl Reminders {
+    pub fn load() -> Self {
+        let Some(yaml) = config_io::load(REMINDERS_FILE) else {
+            return Self::default();
+        };
+        let file = match serde_yaml::from_str::<ScheduleFile>(&yaml) {
+            Ok(file) => file,
+            Err(e) => {
+                log::error!("Failed to parse {REMINDERS_FILE}: {e}");
+                return Self::default();
+            }
+        };
+
+        let now = Local::now();
+        let mut items: Vec<Reminder> = file
+            .reminders
+            .into_iter()
+            .filter_map(|r| {
+                let title = r.title.clone();
+                parse_reminder(r)
+                    .map_err(|e| log::error!("Reminder '{title}': {e}"))
+                    .ok()
+            })
+            .collect();
+
+        let lead = chrono::Duration::minutes(i64::from(file.calendar_lead_minutes));
+        for path in &file.calendars {
+            let path = config_io::get_config_root().join(&**path);
+            match load_ics(&path) {
+                Ok(events) => {
+                    items.extend(events.into_iter().filter_map(|(start, title)| {
+                        let at = start - lead;
+                        (at > now).then(|| Reminder {
+                            title,
+                            body: format!("Starts at {}", start.format("%H:%M")).into(),
+                            schedule: Schedule::Once(at),
+                            next: None,
+                            snoozed: None,
+                        })
+                    }));
+                }
+                Err(e) => log::error!("Failed to load calendar {}: {e:?}", path.display()),
+            }
+        }
+
+        for item in &mut items {
+            item.next = item.schedule.next_after(now);
+        }
+        log::info!("Loaded {} reminders", items.len());
+
+        Self {
+            items,
+            active: None,
+        }
+    }
+
+    /// Queues the first occurrence of every reminder.
+    pub fn schedule_all(&self, tasks: &mut TaskContainer) {
+        for (idx, item) in self.items.iter().enumerate() {
+            if let Some(next) = item.next {
+                enqueue_due(tasks, idx, next);
+            }
+        }
+    }
+
+    /// The active reminder, or else the one coming up next.
+    pub fn next_item(&self) -> Option<(&Reminder, bool)> {
+        if let Some(active) = self.active.and_then(|idx| self.items.get(idx)) {
+            return Some((active, true));
+        }
+        self.items
+            .iter()
+            .filter(|r| r.due().is_some())
+            .min_by_key(|r| r.due())
+            .map(|r| (r, false))
+    }
+}
+
+fn parse_reminder(r: ReminderConfig) -> anyhow::Result<Reminder> {
+    let schedule = match (r.every, r.at.as_deref()) {
+        (Some(minutes), None) => {
+            let Some(every) = minutes_to_duration(minutes) else {
+                anyhow::bail!("'every' must be positive");
+            };
+            Schedule::Every(every)
+        }
+        (None, Some(at)) => {
+            if let Ok(date_time) = NaiveDateTime::parse_from_str(at, "%Y-%m-%d %H:%M") {
+                let Some(at) = Local.from_local_datetime(&date_time).earliest() else {
+                    anyhow::bail!("'{at}' does not exist in the local timezone");
+                };
+                Schedule::Once(at)
+            } else {
+                let time = NaiveTime::parse_from_str(at, "%H:%M")?;
+                let days = r
+                    .days
+                    .iter()
+                    .map(|d| Weekday::from_str(d).map_err(|_| anyhow::anyhow!("bad day '{d}'")))
+                    .collect::<anyhow::Result<_>>()?;
+                Schedule::Daily { time, days }
+            }
+        }
+        _ => anyhow::bail!("needs exactly one of 'every' or 'at'"),
+    };
+
+    Ok(Reminder {
+        title: r.title,
+        body: r.body.unwrap_or_else(|| "".into()),
+        schedule,
+        next: None,
+        snoozed: None,
+    })
+}
+
+/// A positive, representable number of minutes.
+fn minutes_to_duration(minutes: f32) -> Option<chrono::Duration> {
+    if !minutes.is_finite() || minutes <= 0. {
+        return None;
+    }
+    chrono::Duration::try_seconds((minutes * 60.) as i64).filter(|d| *d > chrono::Duration::zero())
+}
+
+fn load_ics(path: &Path) -> anyhow::Result<Vec<(DateTime<Local>, Arc<str>)>> {
+    Ok(parse_ics(&std::fs::read_to_string(path)?))
+}
+
+/// Reads the start time and summary of every timed VEVENT.
+fn parse_ics(text: &str) -> Vec<(DateTime<Local>, Arc<str>)> {
+    // unfold continuation lines
+    let text = text
+        .replace("\r\n", "\n")
+        .replace("\n ", "")
+        .replace("\n\t", "");
+
+    let mut events = Vec::new();
+    let mut start = None;
+    let mut summary: Option<Arc<str>> = None;
+    let mut in_event = false;
+
+    for line in text.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let (name, params) = key.split_once(';').unwrap_or((key, ""));
+        match name {
+            "BEGIN" if value == "VEVENT" => {
+                in_event = true;
+                start = None;
+                summary = None;
+            }
+            "END" if value == "VEVENT" => {
+                in_event = false;
+                if let Some(start) = start.take() {
+                    events.push((start, summary.take().unwrap_or_else(|| "Event".into())));
+                }
+            }
+            "DTSTART" if in_event => start = parse_ics_time(params, value),
+            "SUMMARY" if in_event => summary = Some(unescape_ics_text(value).into()),
+            _ => {}
+        }
+    }
+    events
+}
+
+fn unescape_ics_text(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => out.push('\n'),
+            Some(c) => out.push(c),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+fn parse_ics_time(params: &str, value: &str) -> Option<DateTime<Local>> {
+    // all-day events have no useful time to remind at
+    let date_only = params.contains("VALUE=DATE") && !params.contains("VALUE=DATE-TIME");
+    if date_only || !value.contains('T') {
+        return None;
+    }
+
+    if let Some(utc) = value.strip_suffix('Z') {
+        let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some(Utc.from_utc_datetime(&naive).with_timezone(&Local));
+    }
+
+    let naive = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+
+    let tzid = params
+        .split(';')
+        .find_map(|p| p.strip_prefix("TZID="))
+        .and_then(|tz| tz.parse::<Tz>().ok());
+
+    match tzid {
+        Some(tz) => tz
+            .from_local_datetime(&naive)
+            .earliest()
+            .map(|t| t.with_timezone(&Local)),
+        None => Local.from_local_datetime(&naive).earliest(),
+    }
+}
+
+fn enqueue_due(tasks: &mut TaskContainer, idx: usize, at: DateTime<Local>) {
+    let delay = (at - Local::now()).to_std().unwrap_or(Duration::ZERO);
+    tasks.enqueue_at(
+        TaskType::System(SystemTask::ReminderDue(idx, at)),
+        Instant::now() + delay,
+    );
+}
+
+/// Called when a reminder task comes due.
+pub fn on_reminder_due(app: &mut AppState, idx: usize, at: DateTime<Local>) {
+    let now = Local::now();
+    let due_by = now + chrono::Duration::seconds(DUE_SLACK_SECS);
+    let Some(item) = app.reminders.items.get_mut(idx) else {
+        return;
+    };
+
+    if item.snoozed != Some(at) && item.next != Some(at) {
+        // rescheduled since this task was queued
+        return;
+    }
+    if at > due_by {
+        // the task timer runs on a monotonic clock, which can drift from the wall clock
+        enqueue_due(&mut app.tasks, idx, at);
+        return;
+    }
+
+    let snooze_due = item.snoozed.is_some_and(|t| t <= due_by);
+    let next_due = item.next.is_some_and(|t| t <= due_by);
+
+    if snooze_due {
+        item.snoozed = None;
+    }
+    if next_due {
+        item.next = item.schedule.next_after(now);
+        if let Some(next) = item.next {
+            enqueue_due(&mut app.tasks, idx, next);
+        }
+    }
+
+    log::info!("Reminder: {}", item.title);
+    let toast = Toast::new(ToastTopic::Reminder, item.title.clone(), item.body.clone())
+        .with_timeout(15.)
+        .with_sound(true)
+        .with_action(
+            format!("Snooze {SNOOZE_MINUTES}m").into(),
+            ButtonAction::Reminder {
+                action: ReminderAction::Snooze {
+                    minutes: SNOOZE_MINUTES,
+                },
+            },
+        )
+        .with_action(
+            "Dismiss".into(),
+            ButtonAction::Reminder {
+                action: ReminderAction::Dismiss,
+            },
+        );
+    app.reminders.active = Some(idx);
+    toast.submit(app);
+}
+
+pub fn run_reminder(action: &ReminderAction, app: &mut AppState) {
+    if let ReminderAction::Snooze { minutes } = action {
+        if minutes_to_duration(*minutes).is_none() {
+            log::error!("Invalid snooze duration: {minutes} minutes");
+            return;
+        }
+    }
+    let Some(idx) = app.reminders.active.take() else {
+        return;
+    };
+    let Some(item) = app.reminders.items.get_mut(idx) else {
+        return;
+    };
+
+    match action {
+        ReminderAction::Snooze { minutes } => {
+            let Some(at) =
+                minutes_to_duration(*minutes).and_then(|d| Local::now().checked_add_signed(d))
+            else {
+                return;
+            };
+            item.snoozed = Some(at);
+            enqueue_due(&mut app.tasks, idx, at);
+            Toast::new(
+                ToastTopic::System,
+                format!("Snoozed until {}.", at.format("%H:%M")).into(),
+                item.title.clone(),
+            )
+            .submit(app);
+        }
+        ReminderAction::Dismiss => {
+            item.snoozed = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reminder(yaml: &str) -> anyhow::Result<Reminder> {
+        parse_reminder(serde_yaml::from_str(yaml).unwrap())
+    }
+
+    #[test]
+    fn parses_schedules() {
+        let r = reminder("{ title: Stretch, every: 45 }").unwrap();
+        assert!(matches!(r.schedule, Schedule::Every(d) if d == chrono::Duration::minutes(45)));
+
+        let r = reminder("{ title: Standup, at: '10:00', days: [Mon, Fri] }").unwrap();
+        assert!(matches!(
+            r.schedule,
+            Schedule::Daily { ref days, .. } if days == &[Weekday::Mon, Weekday::Fri]
+        ));
+
+        let r = reminder("{ title: Dentist, at: '2026-11-02 09:30' }").unwrap();
+        assert!(matches!(r.schedule, Schedule::Once(_)));
+    }
+
+    #[test]
+    fn rejects_bad_reminders() {
+        assert!(reminder("{ title: a, every: 0 }").is_err());
+        assert!(reminder("{ title: a, every: -5 }").is_err());
+        assert!(reminder("{ title: a, every: .nan }").is_err());
+        assert!(reminder("{ title: a, every: .inf }").is_err());
+        assert!(reminder("{ title: a }").is_err());
+        assert!(reminder("{ title: a, every: 5, at: '10:00' }").is_err());
+        assert!(reminder("{ title: a, at: '10:00', days: [Someday] }").is_err());
+    }
+
+    #[test]
+    fn snooze_minutes_must_be_positive() {
+        assert_eq!(
+            minutes_to_duration(1.5),
+            Some(chrono::Duration::seconds(90))
+        );
+        assert_eq!(minutes_to_duration(0.), None);
+        assert_eq!(minutes_to_duration(-1.), None);
+        assert_eq!(minutes_to_duration(f32::NAN), None);
+        assert_eq!(minutes_to_duration(f32::INFINITY), None);
+    }
+
+    #[test]
+    fn unescapes_ics_text() {
+        assert_eq!(
+            unescape_ics_text(r"Lunch\, then \;a\nwalk\N\\home"),
+            "Lunch, then ;a\nwalk\n\\home"
+        );
+        assert_eq!(unescape_ics_text("trailing\\"), "trailing\\");
+    }
+
+    #[test]
+    fn parses_ics_events() {
+        let ics = "BEGIN:VCALENDAR\r\n\
+            BEGIN:VEVENT\r\n\
+            DTSTART:20261102T093000Z\r\n\
+            SUMMARY:Den\r\n tist\\, again\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            DTSTART;TZID=Europe/Berlin:20261102T100000\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            DTSTART;VALUE=DATE:20261103\r\n\
+            SUMMARY:Holiday\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            DTSTART:20261104\r\n\
+            SUMMARY:Also all day\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+
+        let events = parse_ics(ics);
+        assert_eq!(events.len(), 2);
+
+        let utc = Utc.with_ymd_and_hms(2026, 11, 2, 9, 30, 0).unwrap();
+        assert_eq!(events[0].0, utc.with_timezone(&Local));
+        assert_eq!(&*events[0].1, "Dentist, again");
+
+        let berlin = chrono_tz::Europe::Berlin
+            .with_ymd_and_hms(2026, 11, 2, 10, 0, 0)
+            .unwrap();
+        assert_eq!(events[1].0, berlin.with_timezone(&Local));
+        assert_eq!(&*events[1].1, "Event");
+    }
+}
@@ -18,17 +18,28 @@ use {
 use crate::backend::osc::OscSender;
 
 use crate::{
-    backend::{input::InputState, overlay::OverlayID, task::TaskContainer},
-    config::{AStrMap, GeneralConfig},
+    backend::{
+        input::InputState,
+        notifications::{DoNotDisturb, NotificationHistory, NotificationRule},
+        overlay::OverlayID,
+        task::TaskContainer,
+    },
+    config::{AStrMap, AStrSet, GeneralConfig},
     config_io,
     graphics::WlxGraphics,
-    gui::font::FontCache,
-    hid::HidProvider,
-    overlays::toast::{DisplayMethod, ToastTopic},
+    gui::{font::FontCache, modular::text_input::TextFocus, theme::Theme},
+    hid::{HidProvider, XkbKeymap},
+    overlays::{
+        keyboard::KeyboardSelection,
+        prediction::WordPredictor,
+        toast::{DisplayMethod, ToastStack, ToastTopic},
+    },
+    reminders::Reminders,
     shaders::{
         frag_color, frag_glyph, frag_grid, frag_line, frag_screen, frag_sprite, frag_sprite2,
         frag_sprite2_hl, frag_srgb, frag_swapchain, vert_common,
     },
+    timers::Timers,
 };
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -51,7 +62,18 @@ pub struct AppState {
     pub anchor: Affine3A,
     pub sprites: AStrMap<Arc<ImageView>>,
     pub keyboard_focus: KeyboardFocus,
+    pub keymap: Option<XkbKeymap>,
+    pub keyboard_selection: KeyboardSelection,
+    pub word_predictor: WordPredictor,
+    pub text_focus: Option<TextFocus>,
     pub toast_sound: &'static [u8],
+    pub theme: Theme,
+    pub custom_panels: AStrSet,
+    pub timers: Timers,
+    pub reminders: Reminders,
+    pub notification_history: NotificationHistory,
+    pub dnd: DoNotDisturb,
+    pub toasts: ToastStack,
 
     #[cfg(feature = "osc")]
     pub osc_sender: Option<OscSender>,
@@ -102,12 +124,8 @@ impl AppState {
             shaders.insert("frag_swapchain", shader);
         }
 
-        #[cfg(feature = "wayvr")]
         let mut tasks = TaskContainer::new();
 
-        #[cfg(not(feature = "wayvr"))]
-        let tasks = TaskContainer::new();
-
         let session = AppSession::load();
 
         #[cfg(feature = "wayvr")]
@@ -123,19 +141,43 @@ impl AppState {
             include_bytes!("res/557297.wav"),
         );
 
+        let theme = Theme::load(&session.config.theme);
+
+        let notification_history =
+            NotificationHistory::load(session.config.notification_history_size);
+
+        let reminders = Reminders::load();
+        reminders.schedule_all(&mut tasks);
+
+        let hid_provider = crate::hid::initialize(
+            session.config.hid_backend,
+            !session.config.touch_screens.is_empty(),
+        );
+
         Ok(Self {
             fc: FontCache::new(session.config.primary_font.clone())?,
             session,
             tasks,
             graphics,
             input_state: InputState::new(),
-            hid_provider: crate::hid::initialize(),
+            hid_provider,
             audio: AudioOutput::new(),
             screens: smallvec![],
             anchor: Affine3A::IDENTITY,
             sprites: AStrMap::new(),
             keyboard_focus: KeyboardFocus::PhysicalScreen,
+            keymap: None,
+            keyboard_selection: KeyboardSelection::default(),
+            word_predictor: WordPredictor::default(),
+            text_focus: None,
             toast_sound: toast_sound_wav,
+            theme,
+            custom_panels: AStrSet::new(),
+            timers: Timers::default(),
+            reminders,
+            notification_history,
+            dnd: DoNotDisturb::default(),
+            toasts: ToastStack::default(),
 
             #[cfg(feature = "osc")]
             osc_sender,
@@ -190,31 +232,53 @@ pub struct AppSession {
     pub wayvr_config: WayVRConfig,
 
     pub toast_topics: IdMap<ToastTopic, DisplayMethod>,
+    pub notification_rules: Vec<NotificationRule>,
 }
 
 impl AppSession {
     pub fn load() -> Self {
         let config_root_path = config_io::ConfigRoot::Generic.ensure_dir();
         log::info!("Config root path: {}", config_root_path.display());
-        let config = GeneralConfig::load_from_disk();
+        #[allow(unused_mut)]
+        let mut config = GeneralConfig::load_from_disk();
 
         let mut toast_topics = IdMap::new();
         toast_topics.insert(ToastTopic::System, DisplayMethod::Center);
         toast_topics.insert(ToastTopic::DesktopNotification, DisplayMethod::Center);
         toast_topics.insert(ToastTopic::XSNotification, DisplayMethod::Center);
+        toast_topics.insert(ToastTopic::OvrtNotification, DisplayMethod::Center);
+        toast_topics.insert(ToastTopic::OvrtWristNotification, DisplayMethod::Watch);
+        toast_topics.insert(ToastTopic::Timer, DisplayMethod::Center);
+        toast_topics.insert(ToastTopic::Reminder, DisplayMethod::Center);
 
         config.notification_topics.iter().for_each(|(k, v)| {
             toast_topics.insert(*k, *v);
         });
 
+        let notification_rules = NotificationRule::load_all(&config.notification_rules);
+
         #[cfg(feature = "wayvr")]
         let wayvr_config = config_wayvr::load_wayvr();
 
+        // these used to live in wayvr.yaml
+        #[cfg(feature = "wayvr")]
+        {
+            if let Some(delay) = wayvr_config.keyboard_repeat_delay {
+                log::warn!("keyboard_repeat_delay in wayvr.yaml is deprecated, set keyboard_repeat_delay_ms in config.yaml instead.");
+                config.keyboard_repeat_delay_ms = delay;
+            }
+            if let Some(rate) = wayvr_config.keyboard_repeat_rate {
+                log::warn!("keyboard_repeat_rate in wayvr.yaml is deprecated, set it in config.yaml instead.");
+                config.keyboard_repeat_rate = rate as f32;
+            }
+        }
+
         Self {
             config,
             #[cfg(feature = "wayvr")]
             wayvr_config,
             toast_topics,
+            notification_rules,
         }
     }
 }
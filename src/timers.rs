@@ -0,0 +1,179 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use serde::Deserialize;
+
+use crate::{
+    backend::{
+        input::Haptics,
+        task::{SystemTask, TaskType},
+    },
+    config::{AStrMap, AStrMapExt},
+    overlays::toast::{Toast, ToastTopic},
+    state::AppState,
+};
+
+const FINISHED_HAPTICS: Haptics = Haptics {
+    intensity: 1.0,
+    duration: 0.5,
+    frequency: 5.0,
+};
+
+#[derive(Deserialize, Clone)]
+pub enum TimerAction {
+    Start,
+    Pause,
+    /// Start if paused, pause if running
+    Toggle,
+    /// Stop and go back to zero, or to the full countdown duration
+    Reset,
+    /// Turn the timer into a countdown of the given length. Resets the timer.
+    SetCountdown {
+        seconds: f32,
+    },
+    /// Turn the timer back into a stopwatch. Resets the timer.
+    ClearCountdown,
+}
+
+/// A stopwatch, or a countdown if `countdown` is set.
+#[derive(Default)]
+pub struct Timer {
+    started: Option<Instant>,
+    accumulated: Duration,
+    countdown: Option<Duration>,
+    /// Bumped on every state change, so that stale completion tasks can be ignored
+    generation: u64,
+}
+
+impl Timer {
+    pub fn elapsed(&self) -> Duration {
+        self.accumulated + self.started.map_or(Duration::ZERO, |s| s.elapsed())
+    }
+
+    /// Time to show: elapsed for stopwatches, remaining for countdowns.
+    /// Countdowns round up to whole seconds, so that 0 only shows once finished.
+    pub fn display(&self) -> Duration {
+        self.remaining().map_or_else(
+            || self.elapsed(),
+            |r| {
+                let secs = r.as_secs() + u64::from(r.subsec_nanos() > 0);
+                Duration::from_secs(secs)
+            },
+        )
+    }
+
+    pub const fn is_running(&self) -> bool {
+        self.started.is_some()
+    }
+
+    fn remaining(&self) -> Option<Duration> {
+        self.countdown.map(|c| c.saturating_sub(self.elapsed()))
+    }
+
+    fn pause(&mut self) {
+        if let Some(started) = self.started.take() {
+            self.accumulated += started.elapsed();
+        }
+    }
+}
+
+/// Named timers. These live in `AppState`, so they keep running while
+/// the panels that display them are rebuilt.
+#[derive(Default)]
+pub struct Timers {
+    timers: AStrMap<Timer>,
+}
+
+impl Timers {
+    pub fn get(&self, id: &str) -> Option<&Timer> {
+        self.timers.arc_get(id)
+    }
+
+    fn get_or_create(&mut self, id: &Arc<str>) -> &mut Timer {
+        let idx = if let Some(idx) = self.timers.iter().position(|(k, _)| k == id) {
+            idx
+        } else {
+            self.timers.push((id.clone(), Timer::default()));
+            self.timers.len() - 1
+        };
+        &mut self.timers[idx].1
+    }
+}
+
+pub fn run_timer(id: &Arc<str>, action: &TimerAction, app: &mut AppState) {
+    let timer = app.timers.get_or_create(id);
+    timer.generation += 1;
+
+    match action {
+        TimerAction::Start => start(timer),
+        TimerAction::Pause => timer.pause(),
+        TimerAction::Toggle => {
+            if timer.is_running() {
+                timer.pause();
+            } else {
+                start(timer);
+            }
+        }
+        TimerAction::Reset => {
+            timer.started = None;
+            timer.accumulated = Duration::ZERO;
+        }
+        TimerAction::SetCountdown { seconds } => {
+            timer.started = None;
+            timer.accumulated = Duration::ZERO;
+            match Duration::try_from_secs_f32(*seconds) {
+                Ok(countdown) => timer.countdown = Some(countdown),
+                Err(e) => log::error!("Invalid countdown of {seconds} seconds for timer {id}: {e}"),
+            }
+        }
+        TimerAction::ClearCountdown => {
+            timer.started = None;
+            timer.accumulated = Duration::ZERO;
+            timer.countdown = None;
+        }
+    }
+
+    if let (Some(remaining), true) = (timer.remaining(), timer.is_running()) {
+        let generation = timer.generation;
+        app.tasks.enqueue_at(
+            TaskType::System(SystemTask::TimerFinished(id.clone(), generation)),
+            Instant::now() + remaining,
+        );
+    }
+}
+
+fn start(timer: &mut Timer) {
+    if timer.started.is_some() {
+        return;
+    }
+    // restarting a finished countdown starts it over
+    if timer.remaining().is_some_and(|r| r.is_zero()) {
+        timer.accumulated = Duration::ZERO;
+    }
+    timer.started = Some(Instant::now());
+}
+
+/// Called when a countdown's completion task comes due.
+pub fn on_timer_finished(app: &mut AppState, id: &Arc<str>, generation: u64) {
+    let Some(timer) = app.timers.timers.iter_mut().find(|(k, _)| k == id) else {
+        return;
+    };
+    let timer = &mut timer.1;
+    if timer.generation != generation || !timer.is_running() {
+        return;
+    }
+
+    timer.pause();
+    if let Some(countdown) = timer.countdown {
+        timer.accumulated = countdown;
+    }
+
+    log::info!("Timer {id} finished");
+    Toast::new(ToastTopic::Timer, "Timer finished".into(), id.clone())
+        .with_timeout(10.)
+        .with_sound(true)
+        .submit(app);
+    app.input_state.pending_haptics = Some(FINISHED_HAPTICS);
+}